@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::linked_list::LinkedList, sync::Arc};
+use alloc::{collections::linked_list::LinkedList, sync::Arc, vec::Vec};
 
 use ostd::{
     mm::{
@@ -14,9 +14,16 @@ use spin::Once;
 
 use crate::dma_pool::{DmaPool, DmaSegment};
 
+/// A buffer of a packet to send.
+///
+/// The buffer starts with a DMA stream holding the header (and, if created by [`TxBuffer::new`],
+/// the packet). A buffer created by [`TxBuffer::from_segments`] is scatter-gather:
+/// the packet is the concatenation of the segments that follow the header, and each
+/// part can be submitted to the device as a separate descriptor.
 pub struct TxBuffer {
     dma_stream: DmaStream,
     nbytes: usize,
+    segments: Vec<DmaSegment>,
     pool: &'static SpinLock<LinkedList<DmaStream>, LocalIrqDisabled>,
 }
 
@@ -31,14 +38,7 @@ impl TxBuffer {
 
         assert!(nbytes <= TX_BUFFER_LEN);
 
-        let dma_stream = if let Some(stream) = pool.lock().pop_front() {
-            stream
-        } else {
-            let segment = FrameAllocOptions::new()
-                .alloc_segment(TX_BUFFER_LEN / PAGE_SIZE)
-                .unwrap();
-            DmaStream::map(segment.into(), DmaDirection::ToDevice, false).unwrap()
-        };
+        let dma_stream = Self::alloc_dma_stream(pool);
 
         let tx_buffer = {
             let mut writer = dma_stream.writer().unwrap();
@@ -47,6 +47,7 @@ impl TxBuffer {
             Self {
                 dma_stream,
                 nbytes,
+                segments: Vec::new(),
                 pool,
             }
         };
@@ -55,6 +56,38 @@ impl TxBuffer {
         tx_buffer
     }
 
+    /// Creates a scatter-gather buffer whose packet is the concatenation of `segments`.
+    ///
+    /// Only the header is copied. The segments are sent as a whole without copying,
+    /// so they must be filled with the packet data beforehand.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if there are more than [`TX_MAX_SEGMENTS`] segments.
+    pub fn from_segments<H: Pod>(
+        header: &H,
+        segments: Vec<DmaSegment>,
+        pool: &'static SpinLock<LinkedList<DmaStream>, LocalIrqDisabled>,
+    ) -> Self {
+        assert!(segments.len() <= TX_MAX_SEGMENTS);
+
+        let mut tx_buffer = Self::new(header, &[], pool);
+        tx_buffer.segments = segments;
+        tx_buffer
+    }
+
+    fn alloc_dma_stream(
+        pool: &'static SpinLock<LinkedList<DmaStream>, LocalIrqDisabled>,
+    ) -> DmaStream {
+        if let Some(stream) = pool.lock().pop_front() {
+            return stream;
+        }
+        let segment = FrameAllocOptions::new()
+            .alloc_segment(TX_BUFFER_LEN / PAGE_SIZE)
+            .unwrap();
+        DmaStream::map(segment.into(), DmaDirection::ToDevice, false).unwrap()
+    }
+
     pub fn writer(&self) -> VmWriter<'_, Infallible> {
         self.dma_stream.writer().unwrap().limit(self.nbytes)
     }
@@ -63,9 +96,27 @@ impl TxBuffer {
         self.dma_stream.sync(0..self.nbytes).unwrap();
     }
 
+    /// Returns the number of bytes in the DMA stream, i.e., excluding the segments.
     pub fn nbytes(&self) -> usize {
         self.nbytes
     }
+
+    /// Returns the segments that follow the DMA stream.
+    ///
+    /// This is empty unless the buffer is created by [`TxBuffer::from_segments`].
+    pub fn segments(&self) -> &[DmaSegment] {
+        &self.segments
+    }
+
+    /// Returns the number of bytes of the whole buffer, including the segments.
+    pub fn total_nbytes(&self) -> usize {
+        self.nbytes
+            + self
+                .segments
+                .iter()
+                .map(|segment| segment.size())
+                .sum::<usize>()
+    }
 }
 
 impl HasDaddr for TxBuffer {
@@ -140,6 +191,8 @@ impl HasDaddr for RxBuffer {
 
 pub const RX_BUFFER_LEN: usize = 4096;
 pub const TX_BUFFER_LEN: usize = 4096;
+/// The maximum number of segments in a scatter-gather [`TxBuffer`].
+pub const TX_MAX_SEGMENTS: usize = 16;
 pub static RX_BUFFER_POOL: Once<Arc<DmaPool>> = Once::new();
 
 pub fn init() {
@@ -155,3 +208,50 @@ pub fn init() {
         )
     });
 }
+
+#[cfg(ktest)]
+mod test {
+    use alloc::vec;
+    use core::mem::size_of;
+
+    use ostd::prelude::*;
+
+    use super::*;
+
+    static POOL: SpinLock<LinkedList<DmaStream>, LocalIrqDisabled> =
+        SpinLock::new(LinkedList::new());
+
+    #[ktest]
+    fn scatter_gather_tx_buffer() {
+        const SEGMENT_SIZE: usize = PAGE_SIZE;
+
+        let segment_pool = DmaPool::new(SEGMENT_SIZE, 2, 2, DmaDirection::Bidirectional, false);
+        let mut reference = Vec::new();
+        let segments: Vec<_> = (1..=2u8)
+            .map(|fill| {
+                let segment = segment_pool.alloc_segment().unwrap();
+                let data = vec![fill; SEGMENT_SIZE];
+                segment
+                    .writer()
+                    .unwrap()
+                    .write(&mut VmReader::from(data.as_slice()));
+                reference.extend_from_slice(&data);
+                segment
+            })
+            .collect();
+
+        let header = 0xdead_beef_u32;
+        let tx_buffer = TxBuffer::from_segments(&header, segments, &POOL);
+        assert_eq!(tx_buffer.nbytes(), size_of::<u32>());
+        assert_eq!(tx_buffer.segments().len(), 2);
+        assert_eq!(tx_buffer.total_nbytes(), size_of::<u32>() + reference.len());
+
+        // The header lives in a device-only DMA stream, so only the payload can be read back.
+        let mut payload = vec![0u8; reference.len()];
+        let mut writer = VmWriter::from(payload.as_mut_slice());
+        for segment in tx_buffer.segments() {
+            segment.reader().unwrap().read(&mut writer);
+        }
+        assert_eq!(payload, reference);
+    }
+}
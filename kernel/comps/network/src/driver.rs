@@ -6,6 +6,7 @@ use aster_bigtcp::{
     device::{self, NotifyDevice},
     time::Instant,
 };
+use log::warn;
 use ostd::mm::VmWriter;
 
 use crate::{buffer::RxBuffer, AnyNetworkDevice};
@@ -15,12 +16,12 @@ impl device::Device for dyn AnyNetworkDevice {
     type TxToken<'a> = TxToken<'a>;
 
     fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
-        if self.can_receive() && self.can_send() {
-            let rx_buffer = self.receive().unwrap();
-            Some((RxToken(rx_buffer), TxToken(self)))
-        } else {
-            None
+        if !(self.can_receive() && self.can_send()) {
+            return None;
         }
+
+        let rx_buffer = self.receive().ok()?;
+        Some((RxToken(rx_buffer), TxToken(self)))
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
@@ -65,7 +66,11 @@ impl device::TxToken for TxToken<'_> {
     {
         let mut buffer = vec![0u8; len];
         let res = f(&mut buffer);
-        self.0.send(&buffer).expect("Send packet failed");
+        if let Err(err) = self.0.send(&buffer) {
+            // The packet is dropped, which is allowed since the upper layers
+            // will retransmit the lost packets if necessary.
+            warn!("failed to send packet: {:?}", err);
+        }
         res
     }
 }
@@ -9,6 +9,7 @@
 mod buffer;
 pub mod dma_pool;
 mod driver;
+mod stats;
 
 extern crate alloc;
 
@@ -16,7 +17,7 @@ use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 use core::{any::Any, fmt::Debug};
 
 use aster_bigtcp::device::DeviceCapabilities;
-pub use buffer::{RxBuffer, TxBuffer, RX_BUFFER_POOL, TX_BUFFER_LEN};
+pub use buffer::{RxBuffer, TxBuffer, RX_BUFFER_POOL, TX_BUFFER_LEN, TX_MAX_SEGMENTS};
 use component::{init_component, ComponentInitError};
 pub use dma_pool::DmaSegment;
 use ostd::{
@@ -24,11 +25,41 @@ use ostd::{
     Pod,
 };
 use spin::Once;
+pub use stats::{NetStats, NetStatsCounter};
 
-#[derive(Debug, Clone, Copy, Pod)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Pod)]
 #[repr(C)]
 pub struct EthernetAddr(pub [u8; 6]);
 
+impl EthernetAddr {
+    /// The broadcast address.
+    pub const BROADCAST: Self = Self([0xff; 6]);
+
+    /// Returns whether the address is the broadcast address.
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// Returns whether the address is a multicast address.
+    ///
+    /// Note that the broadcast address is also a multicast address.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Returns whether the address is a unicast address.
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// Computes the Jenkins hash of the address.
+    ///
+    /// This is useful for building MAC-keyed hash tables, e.g., for bridging.
+    pub fn jhash(&self, initval: u32) -> u32 {
+        jhash::jhash_slice(&self.0, initval)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum VirtioNetError {
     NotReady,
@@ -43,6 +74,18 @@ pub trait AnyNetworkDevice: Send + Sync + Any + Debug {
     fn mac_addr(&self) -> EthernetAddr;
     fn capabilities(&self) -> DeviceCapabilities;
 
+    /// Returns the packet statistics counters of the device.
+    ///
+    /// The driver only needs to own the counters.
+    /// They are updated by [`Self::receive`], [`Self::receive_batch`], [`Self::send`]
+    /// and [`Self::send_segments`], through which all the packets are received or sent.
+    fn stats_counter(&self) -> &NetStatsCounter;
+
+    /// Returns a snapshot of the packet statistics of the device.
+    fn interface_stats(&self) -> NetStats {
+        self.stats_counter().snapshot()
+    }
+
     // ================Device Operation===================
 
     fn can_receive(&self) -> bool;
@@ -50,10 +93,89 @@ pub trait AnyNetworkDevice: Send + Sync + Any + Debug {
 
     /// Receives a packet from network. If packet is ready, returns a `RxBuffer` containing the packet.
     /// Otherwise, return [`VirtioNetError::NotReady`].
-    fn receive(&mut self) -> Result<RxBuffer, VirtioNetError>;
+    ///
+    /// This is the driver hook behind [`Self::receive`], which should be called instead.
+    fn raw_receive(&mut self) -> Result<RxBuffer, VirtioNetError>;
+
+    /// Receives at most `max` packets from network.
+    ///
+    /// This is the driver hook behind [`Self::receive_batch`], which should be called instead.
+    /// The default implementation calls [`Self::raw_receive`] until it fails or `max` packets
+    /// have been received. Drivers that can drain their receive queue in one shot should
+    /// override this method.
+    fn raw_receive_batch(&mut self, max: usize) -> Vec<RxBuffer> {
+        let mut rx_buffers = Vec::new();
+        while rx_buffers.len() < max {
+            let Ok(rx_buffer) = self.raw_receive() else {
+                break;
+            };
+            rx_buffers.push(rx_buffer);
+        }
+        rx_buffers
+    }
 
     /// Sends a packet to network.
-    fn send(&mut self, packet: &[u8]) -> Result<(), VirtioNetError>;
+    ///
+    /// This is the driver hook behind [`Self::send`], which should be called instead.
+    fn raw_send(&mut self, packet: &[u8]) -> Result<(), VirtioNetError>;
+
+    /// Sends a packet that is the concatenation of `segments` to network.
+    ///
+    /// This is the driver hook behind [`Self::send_segments`], which should be called instead.
+    /// Each segment is sent as a whole without copying. At most [`TX_MAX_SEGMENTS`]
+    /// segments are allowed; otherwise, [`VirtioNetError::Unknown`] is returned.
+    fn raw_send_segments(&mut self, segments: Vec<DmaSegment>) -> Result<(), VirtioNetError>;
+
+    /// Receives a packet from network and counts it in the statistics.
+    ///
+    /// Drivers should implement [`Self::raw_receive`] instead of overriding this method.
+    fn receive(&mut self) -> Result<RxBuffer, VirtioNetError> {
+        let res = self.raw_receive();
+        match &res {
+            Ok(rx_buffer) => self.stats_counter().count_rx(rx_buffer.packet_len()),
+            // Having no packet to receive is not an error.
+            Err(VirtioNetError::NotReady) => {}
+            Err(_) => self.stats_counter().count_rx_error(),
+        }
+        res
+    }
+
+    /// Receives at most `max` packets from network and counts them in the statistics.
+    ///
+    /// Drivers should implement [`Self::raw_receive_batch`] instead of overriding this method.
+    fn receive_batch(&mut self, max: usize) -> Vec<RxBuffer> {
+        let rx_buffers = self.raw_receive_batch(max);
+        for rx_buffer in rx_buffers.iter() {
+            self.stats_counter().count_rx(rx_buffer.packet_len());
+        }
+        rx_buffers
+    }
+
+    /// Sends a packet to network and counts it in the statistics.
+    ///
+    /// Drivers should implement [`Self::raw_send`] instead of overriding this method.
+    fn send(&mut self, packet: &[u8]) -> Result<(), VirtioNetError> {
+        let res = self.raw_send(packet);
+        match res {
+            Ok(()) => self.stats_counter().count_tx(packet.len()),
+            Err(_) => self.stats_counter().count_tx_error(),
+        }
+        res
+    }
+
+    /// Sends a packet that is the concatenation of `segments` to network and counts it
+    /// in the statistics.
+    ///
+    /// Drivers should implement [`Self::raw_send_segments`] instead of overriding this method.
+    fn send_segments(&mut self, segments: Vec<DmaSegment>) -> Result<(), VirtioNetError> {
+        let len = segments.iter().map(DmaSegment::size).sum();
+        let res = self.raw_send_segments(segments);
+        match res {
+            Ok(()) => self.stats_counter().count_tx(len),
+            Err(_) => self.stats_counter().count_tx_error(),
+        }
+        res
+    }
 
     /// Frees processes tx buffers.
     fn free_processed_tx_buffers(&mut self);
@@ -63,6 +185,10 @@ pub trait AnyNetworkDevice: Send + Sync + Any + Debug {
     /// The driver can assume that the device remains protected by acquiring a poll lock
     /// for the entire duration of the polling process.
     /// Thus two polling process cannot happen simultaneously.
+    ///
+    /// A polling process may receive packets one by one with [`Self::receive`] or
+    /// in batches with [`Self::receive_batch`]. Either way, the driver may defer
+    /// refilling its receive queue and notifying the device until this method is called.
     fn notify_poll_end(&mut self);
 }
 
@@ -80,6 +206,26 @@ pub fn register_device(
         .insert(name, NetworkDeviceIrqCallbackSet::new(device));
 }
 
+/// Unregisters the network device of the given name.
+///
+/// The device is removed from the device table together with its send and recv callbacks.
+/// Returns the removed device, or `None` if no device of the given name is registered.
+///
+/// The removal is done with the device table locked, so [`get_device`], [`all_devices`]
+/// and the IRQ handlers either observe the device with all its callbacks or not at all.
+/// However, the caller must ensure that the device no longer raises interrupts
+/// (e.g., by resetting the device) before dropping the returned device,
+/// since a handler that has already looked up the device may still be running.
+pub fn unregister_device(name: &str) -> Option<NetworkDeviceRef> {
+    let callbacks = COMPONENT
+        .get()
+        .unwrap()
+        .network_device_table
+        .lock()
+        .remove(name)?;
+    Some(callbacks.device)
+}
+
 pub fn get_device(str: &str) -> Option<Arc<SpinLock<dyn AnyNetworkDevice, LocalIrqDisabled>>> {
     let table = COMPONENT.get().unwrap().network_device_table.lock();
     let callbacks = table.get(str)?;
@@ -139,6 +285,13 @@ pub fn handle_send_irq(name: &str) {
     }
 }
 
+/// Returns the packet statistics of the network device of the given name.
+pub fn get_stats(name: &str) -> Option<NetStats> {
+    let device = get_device(name)?;
+    let stats = device.lock().interface_stats();
+    Some(stats)
+}
+
 pub fn all_devices() -> Vec<(String, NetworkDeviceRef)> {
     let network_devs = COMPONENT.get().unwrap().network_device_table.lock();
     network_devs
@@ -194,3 +347,196 @@ impl Component {
         })
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use alloc::string::ToString;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use ostd::prelude::*;
+
+    use super::*;
+
+    fn init() {
+        static DONE: AtomicBool = AtomicBool::new(false);
+        if !DONE.load(Ordering::SeqCst) {
+            let _ = super::init();
+            DONE.store(true, Ordering::SeqCst);
+        }
+    }
+
+    const MOCK_PACKET_LEN: usize = 64;
+
+    /// A mock device that has `nr_pending_rx` packets to receive.
+    #[derive(Debug, Default)]
+    struct MockDevice {
+        nr_pending_rx: usize,
+        stats: NetStatsCounter,
+    }
+
+    impl AnyNetworkDevice for MockDevice {
+        fn mac_addr(&self) -> EthernetAddr {
+            EthernetAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56])
+        }
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            DeviceCapabilities::default()
+        }
+
+        fn stats_counter(&self) -> &NetStatsCounter {
+            &self.stats
+        }
+
+        fn can_receive(&self) -> bool {
+            self.nr_pending_rx > 0
+        }
+
+        fn can_send(&self) -> bool {
+            true
+        }
+
+        fn raw_receive(&mut self) -> Result<RxBuffer, VirtioNetError> {
+            if self.nr_pending_rx == 0 {
+                return Err(VirtioNetError::NotReady);
+            }
+            self.nr_pending_rx -= 1;
+            let mut rx_buffer = RxBuffer::new(0, RX_BUFFER_POOL.get().unwrap());
+            rx_buffer.set_packet_len(MOCK_PACKET_LEN);
+            Ok(rx_buffer)
+        }
+
+        fn raw_send(&mut self, _packet: &[u8]) -> Result<(), VirtioNetError> {
+            Ok(())
+        }
+
+        fn raw_send_segments(&mut self, _segments: Vec<DmaSegment>) -> Result<(), VirtioNetError> {
+            Ok(())
+        }
+
+        fn free_processed_tx_buffers(&mut self) {}
+
+        fn notify_poll_end(&mut self) {}
+    }
+
+    #[ktest]
+    fn ethernet_addr_classification() {
+        let broadcast = EthernetAddr::BROADCAST;
+        assert!(broadcast.is_broadcast());
+        assert!(broadcast.is_multicast());
+        assert!(!broadcast.is_unicast());
+
+        let multicast = EthernetAddr([0x01, 0x00, 0x5e, 0x00, 0x00, 0x01]);
+        assert!(!multicast.is_broadcast());
+        assert!(multicast.is_multicast());
+        assert!(!multicast.is_unicast());
+
+        let unicast = EthernetAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        assert!(!unicast.is_broadcast());
+        assert!(!unicast.is_multicast());
+        assert!(unicast.is_unicast());
+    }
+
+    #[ktest]
+    fn ethernet_addr_jhash() {
+        const INITVAL: u32 = 0x1234;
+
+        let addr = EthernetAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        let same_addr = EthernetAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        let other_addr = EthernetAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x57]);
+
+        assert_eq!(addr, same_addr);
+        assert_eq!(addr.jhash(INITVAL), same_addr.jhash(INITVAL));
+        assert_ne!(addr, other_addr);
+        assert_ne!(addr.jhash(INITVAL), other_addr.jhash(INITVAL));
+
+        let mut table = BTreeMap::new();
+        table.insert(addr, 1);
+        assert_eq!(table.get(&same_addr), Some(&1));
+        assert_eq!(table.get(&other_addr), None);
+    }
+
+    #[ktest]
+    fn register_and_unregister_device() {
+        const NAME: &str = "mock-unregister";
+
+        init();
+        register_device(
+            NAME.to_string(),
+            Arc::new(SpinLock::new(MockDevice::default())),
+        );
+        register_recv_callback(NAME, || {});
+        assert!(get_device(NAME).is_some());
+        assert!(all_devices().iter().any(|(name, _)| name == NAME));
+
+        assert!(unregister_device(NAME).is_some());
+        assert!(get_device(NAME).is_none());
+        assert!(!all_devices().iter().any(|(name, _)| name == NAME));
+        assert!(unregister_device(NAME).is_none());
+    }
+
+    #[ktest]
+    fn receive_batch_stops_when_not_ready() {
+        init();
+        let mut device = MockDevice {
+            nr_pending_rx: 3,
+            ..Default::default()
+        };
+        assert_eq!(device.receive_batch(10).len(), 3);
+        assert!(device.receive_batch(10).is_empty());
+
+        let mut device = MockDevice {
+            nr_pending_rx: 3,
+            ..Default::default()
+        };
+        assert_eq!(device.receive_batch(2).len(), 2);
+        assert_eq!(device.receive_batch(2).len(), 1);
+    }
+
+    #[ktest]
+    fn count_packets_through_device() {
+        use aster_bigtcp::{
+            device::{Device, RxToken, TxToken},
+            time::Instant,
+        };
+        use ostd::mm::DmaDirection;
+
+        use crate::dma_pool::DmaPool;
+
+        const NAME: &str = "mock-stats";
+        const SEGMENT_SIZE: usize = 128;
+
+        init();
+        let segment_pool = DmaPool::new(SEGMENT_SIZE, 2, 2, DmaDirection::ToDevice, false);
+        let device = Arc::new(SpinLock::new(MockDevice {
+            nr_pending_rx: 4,
+            ..Default::default()
+        }));
+        register_device(NAME.to_string(), device.clone());
+
+        {
+            let mut device = device.lock();
+            let device: &mut dyn AnyNetworkDevice = &mut *device;
+            assert_eq!(device.receive_batch(2).len(), 2);
+            while let Some((rx_token, _)) = Device::receive(device, Instant::ZERO) {
+                rx_token.consume(|_| ());
+            }
+            let tx_token = Device::transmit(device, Instant::ZERO).unwrap();
+            tx_token.consume(60, |buf| buf.fill(0));
+            let segments = (0..2)
+                .map(|_| segment_pool.alloc_segment().unwrap())
+                .collect();
+            device.send_segments(segments).unwrap();
+        }
+
+        let stats = get_stats(NAME).unwrap();
+        assert_eq!(stats.rx_packets, 4);
+        assert_eq!(stats.rx_bytes, 4 * MOCK_PACKET_LEN as u64);
+        assert_eq!(stats.tx_packets, 2);
+        assert_eq!(stats.tx_bytes, 60 + 2 * SEGMENT_SIZE as u64);
+        assert_eq!(stats.rx_errors, 0);
+        assert_eq!(stats.tx_errors, 0);
+
+        unregister_device(NAME);
+        assert!(get_stats(NAME).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the packet statistics of a network device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NetStats {
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+}
+
+/// The packet statistics counters of a network device.
+///
+/// A driver only owns the counters and exposes them via
+/// [`AnyNetworkDevice::stats_counter`](crate::AnyNetworkDevice::stats_counter).
+/// It is this component that updates the counters
+/// whenever a packet is received or sent through the device.
+#[derive(Debug, Default)]
+pub struct NetStatsCounter {
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
+    rx_errors: AtomicU64,
+    tx_errors: AtomicU64,
+}
+
+impl NetStatsCounter {
+    /// Creates a new counter with all statistics set to zero.
+    pub const fn new() -> Self {
+        Self {
+            rx_packets: AtomicU64::new(0),
+            rx_bytes: AtomicU64::new(0),
+            tx_packets: AtomicU64::new(0),
+            tx_bytes: AtomicU64::new(0),
+            rx_errors: AtomicU64::new(0),
+            tx_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Takes a snapshot of the statistics.
+    pub fn snapshot(&self) -> NetStats {
+        NetStats {
+            rx_packets: self.rx_packets.load(Ordering::Relaxed),
+            rx_bytes: self.rx_bytes.load(Ordering::Relaxed),
+            tx_packets: self.tx_packets.load(Ordering::Relaxed),
+            tx_bytes: self.tx_bytes.load(Ordering::Relaxed),
+            rx_errors: self.rx_errors.load(Ordering::Relaxed),
+            tx_errors: self.tx_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn count_rx(&self, nbytes: usize) {
+        self.rx_packets.fetch_add(1, Ordering::Relaxed);
+        self.rx_bytes.fetch_add(nbytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_tx(&self, nbytes: usize) {
+        self.tx_packets.fetch_add(1, Ordering::Relaxed);
+        self.tx_bytes.fetch_add(nbytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_rx_error(&self) {
+        self.rx_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_tx_error(&self) {
+        self.tx_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -38,6 +38,36 @@ pub enum VirtioDeviceType {
     Memory = 24,
 }
 
+impl VirtioDeviceType {
+    /// Returns the device type of a virtio-pci device given its PCI device ID.
+    ///
+    /// Both transitional device IDs (`0x1000..0x1040`) and modern device IDs
+    /// (`0x1040 + device type`) are recognized. Returns `None` if the ID does not
+    /// belong to any known virtio device.
+    pub fn from_device_id(device_id: u16) -> Option<Self> {
+        const MODERN_ID_BASE: u16 = 0x1040;
+
+        let device_type = match device_id {
+            0x1000 => Self::Network,
+            0x1001 => Self::Block,
+            0x1002 => Self::TraditionalMemoryBalloon,
+            0x1003 => Self::Console,
+            0x1004 => Self::ScsiHost,
+            0x1005 => Self::Entropy,
+            0x1009 => Self::Transport9P,
+            MODERN_ID_BASE.. => {
+                let type_id = u8::try_from(device_id - MODERN_ID_BASE).ok()?;
+                Self::try_from(type_id).ok()?
+            }
+            _ => return None,
+        };
+        if device_type == Self::Invalid {
+            return None;
+        }
+        Some(device_type)
+    }
+}
+
 #[derive(Debug)]
 pub enum VirtioDeviceError {
     /// queues amount do not match the requirement
@@ -54,3 +84,63 @@ impl From<QueueError> for VirtioDeviceError {
         VirtioDeviceError::QueueUnknownError
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn device_type_from_transitional_id() {
+        let cases = [
+            (0x1000, VirtioDeviceType::Network),
+            (0x1001, VirtioDeviceType::Block),
+            (0x1002, VirtioDeviceType::TraditionalMemoryBalloon),
+            (0x1003, VirtioDeviceType::Console),
+            (0x1004, VirtioDeviceType::ScsiHost),
+            (0x1005, VirtioDeviceType::Entropy),
+            (0x1009, VirtioDeviceType::Transport9P),
+        ];
+        for (device_id, device_type) in cases {
+            assert_eq!(
+                VirtioDeviceType::from_device_id(device_id),
+                Some(device_type)
+            );
+        }
+        assert_eq!(VirtioDeviceType::from_device_id(0x1006), None);
+        assert_eq!(VirtioDeviceType::from_device_id(0x103f), None);
+    }
+
+    #[ktest]
+    fn device_type_from_modern_id() {
+        let cases = [
+            (0x1041, VirtioDeviceType::Network),
+            (0x1042, VirtioDeviceType::Block),
+            (0x1043, VirtioDeviceType::Console),
+            (0x1044, VirtioDeviceType::Entropy),
+            (0x1045, VirtioDeviceType::TraditionalMemoryBalloon),
+            (0x1048, VirtioDeviceType::ScsiHost),
+            (0x1049, VirtioDeviceType::Transport9P),
+            (0x1050, VirtioDeviceType::GPU),
+            (0x1052, VirtioDeviceType::Input),
+            (0x1053, VirtioDeviceType::Socket),
+            (0x1054, VirtioDeviceType::Crypto),
+            (0x1058, VirtioDeviceType::Memory),
+        ];
+        for (device_id, device_type) in cases {
+            assert_eq!(
+                VirtioDeviceType::from_device_id(device_id),
+                Some(device_type)
+            );
+        }
+        // `Invalid` is never a device type.
+        assert_eq!(VirtioDeviceType::from_device_id(0x1040), None);
+        // Reserved type IDs.
+        assert_eq!(VirtioDeviceType::from_device_id(0x104e), None);
+        assert_eq!(VirtioDeviceType::from_device_id(0x1059), None);
+        // Type IDs beyond `u8` must not wrap around.
+        assert_eq!(VirtioDeviceType::from_device_id(0x1141), None);
+        assert_eq!(VirtioDeviceType::from_device_id(0x0fff), None);
+    }
+}
@@ -7,7 +7,8 @@ use core::{fmt::Debug, mem::size_of};
 
 use aster_bigtcp::device::{Checksum, DeviceCapabilities, Medium};
 use aster_network::{
-    AnyNetworkDevice, EthernetAddr, RxBuffer, TxBuffer, VirtioNetError, RX_BUFFER_POOL,
+    AnyNetworkDevice, DmaSegment, EthernetAddr, NetStatsCounter, RxBuffer, TxBuffer,
+    VirtioNetError, RX_BUFFER_POOL, TX_MAX_SEGMENTS,
 };
 use aster_util::slot_vec::SlotVec;
 use log::{debug, warn};
@@ -20,6 +21,7 @@ use ostd::{
 use super::{config::VirtioNetConfig, header::VirtioNetHdr};
 use crate::{
     device::{network::config::NetworkFeatures, VirtioDeviceError},
+    dma_buf::DmaBuf,
     queue::{QueueError, VirtQueue},
     transport::{ConfigManager, VirtioTransport},
 };
@@ -38,6 +40,7 @@ pub struct NetworkDevice {
     rx_buffers: SlotVec<RxBuffer>,
     transport: Box<dyn VirtioTransport>,
     poll_stat: PollStatistics,
+    stats: NetStatsCounter,
 }
 
 /// Structure to track the number of packets sent and received during a single polling process.
@@ -118,6 +121,7 @@ impl NetworkDevice {
             rx_buffers,
             transport,
             poll_stat: PollStatistics::new(),
+            stats: NetStatsCounter::new(),
         };
 
         /// Interrupt handler if network device config space changes
@@ -204,6 +208,49 @@ impl NetworkDevice {
             .add_dma_buf(&[&tx_buffer], &[])
             .map_err(queue_to_network_error)?;
 
+        debug!("send packet, token = {}, len = {}", token, packet.len());
+        self.finish_send(token, tx_buffer);
+
+        Ok(())
+    }
+
+    /// Sends a packet that is the concatenation of `segments` to network.
+    fn send_segments(&mut self, segments: Vec<DmaSegment>) -> Result<(), VirtioNetError> {
+        if segments.len() > TX_MAX_SEGMENTS {
+            return Err(VirtioNetError::Unknown);
+        }
+        if self.send_queue.available_desc() < segments.len() + 1 {
+            return Err(VirtioNetError::Busy);
+        }
+
+        let tx_buffer = TxBuffer::from_segments(&self.header, segments, &TX_BUFFER_POOL);
+
+        // The virtio net header goes first, followed by one descriptor per segment.
+        let inputs: Vec<&dyn DmaBuf> = core::iter::once(&tx_buffer as &dyn DmaBuf)
+            .chain(
+                tx_buffer
+                    .segments()
+                    .iter()
+                    .map(|segment| segment as &dyn DmaBuf),
+            )
+            .collect();
+        let token = self
+            .send_queue
+            .add_dma_buf(&inputs, &[])
+            .map_err(queue_to_network_error)?;
+
+        debug!(
+            "send packet, token = {}, len = {}",
+            token,
+            tx_buffer.total_nbytes() - tx_buffer.nbytes()
+        );
+        self.finish_send(token, tx_buffer);
+
+        Ok(())
+    }
+
+    /// Keeps the buffer of a submitted packet until the device finishes sending it.
+    fn finish_send(&mut self, token: u16, tx_buffer: TxBuffer) {
         self.poll_stat.sent_packet += 1;
 
         if self.send_queue.available_desc() == 0 {
@@ -212,8 +259,6 @@ impl NetworkDevice {
             self.notify_send_queue();
         }
 
-        debug!("send packet, token = {}, len = {}", token, packet.len());
-
         debug_assert!(self.tx_buffers[token as usize].is_none());
         self.tx_buffers[token as usize] = Some(tx_buffer);
 
@@ -229,8 +274,6 @@ impl NetworkDevice {
         } else {
             self.send_queue.disable_callback();
         }
-
-        Ok(())
     }
 
     fn notify_send_queue(&mut self) {
@@ -323,6 +366,10 @@ impl AnyNetworkDevice for NetworkDevice {
         self.caps.clone()
     }
 
+    fn stats_counter(&self) -> &NetStatsCounter {
+        &self.stats
+    }
+
     fn can_receive(&self) -> bool {
         self.recv_queue.can_pop()
     }
@@ -331,14 +378,18 @@ impl AnyNetworkDevice for NetworkDevice {
         self.send_queue.available_desc() >= 1
     }
 
-    fn receive(&mut self) -> Result<RxBuffer, VirtioNetError> {
+    fn raw_receive(&mut self) -> Result<RxBuffer, VirtioNetError> {
         self.receive()
     }
 
-    fn send(&mut self, packet: &[u8]) -> Result<(), VirtioNetError> {
+    fn raw_send(&mut self, packet: &[u8]) -> Result<(), VirtioNetError> {
         self.send(packet)
     }
 
+    fn raw_send_segments(&mut self, segments: Vec<DmaSegment>) -> Result<(), VirtioNetError> {
+        self.send_segments(segments)
+    }
+
     fn free_processed_tx_buffers(&mut self) {
         while let Ok((token, _)) = self.send_queue.pop_used() {
             self.tx_buffers[token as usize] = None;
@@ -144,6 +144,26 @@ impl VirtQueue {
         transport
             .set_queue(idx, size, &descriptor_ptr, &avail_ring_ptr, &used_ring_ptr)
             .unwrap();
+        let notify_config = transport.notify_config(idx as usize);
+        Ok(Self::with_rings(
+            idx,
+            size,
+            descriptor_ptr,
+            avail_ring_ptr,
+            used_ring_ptr,
+            notify_config,
+        ))
+    }
+
+    /// Creates a VirtQueue over the rings that have been set to the device.
+    fn with_rings(
+        idx: u16,
+        size: u16,
+        descriptor_ptr: SafePtr<Descriptor, DmaCoherent>,
+        avail_ring_ptr: SafePtr<AvailRing, DmaCoherent>,
+        used_ring_ptr: SafePtr<UsedRing, DmaCoherent>,
+        notify_config: ConfigManager<u32>,
+    ) -> Self {
         let mut descs = Vec::with_capacity(size as usize);
         descs.push(descriptor_ptr);
         for i in 0..size {
@@ -162,11 +182,10 @@ impl VirtQueue {
             }
         }
 
-        let notify_config = transport.notify_config(idx as usize);
         field_ptr!(&avail_ring_ptr, AvailRing, flags)
             .write_once(&AvailFlags::empty())
             .unwrap();
-        Ok(VirtQueue {
+        VirtQueue {
             descs,
             avail: avail_ring_ptr,
             used: used_ring_ptr,
@@ -178,13 +197,13 @@ impl VirtQueue {
             avail_idx: 0,
             last_used_idx: 0,
             is_callback_enabled: true,
-        })
+        }
     }
 
     /// Add dma buffers to the virtqueue, return a token.
     ///
     /// Ref: linux virtio_ring.c virtqueue_add
-    pub fn add_dma_buf<T: DmaBuf>(
+    pub fn add_dma_buf<T: DmaBuf + ?Sized>(
         &mut self,
         inputs: &[&T],
         outputs: &[&T],
@@ -306,14 +325,7 @@ impl VirtQueue {
             return Err(QueueError::NotReady);
         }
 
-        let last_used_slot = self.last_used_idx & (self.queue_size - 1);
-        let element_ptr = {
-            let mut ptr = self.used.borrow_vm();
-            ptr.byte_add(offset_of!(UsedRing, ring) as usize + last_used_slot as usize * 8);
-            ptr.cast::<UsedElem>()
-        };
-        let index = field_ptr!(&element_ptr, UsedElem, id).read_once().unwrap();
-        let len = field_ptr!(&element_ptr, UsedElem, len).read_once().unwrap();
+        let (index, len) = self.last_used_elem();
 
         self.recycle_descriptors(index as u16);
         self.last_used_idx = self.last_used_idx.wrapping_add(1);
@@ -330,14 +342,7 @@ impl VirtQueue {
             return Err(QueueError::NotReady);
         }
 
-        let last_used_slot = self.last_used_idx & (self.queue_size - 1);
-        let element_ptr = {
-            let mut ptr = self.used.borrow_vm();
-            ptr.byte_add(offset_of!(UsedRing, ring) as usize + last_used_slot as usize * 8);
-            ptr.cast::<UsedElem>()
-        };
-        let index = field_ptr!(&element_ptr, UsedElem, id).read_once().unwrap();
-        let len = field_ptr!(&element_ptr, UsedElem, len).read_once().unwrap();
+        let (index, len) = self.last_used_elem();
 
         if index as u16 != token {
             return Err(QueueError::WrongToken);
@@ -349,6 +354,40 @@ impl VirtQueue {
         Ok(len)
     }
 
+    /// Pops at most `max` tokens from device used buffers, returning (token, len) pairs.
+    ///
+    /// Unlike calling [`Self::pop_used`] repeatedly, the index of the used ring
+    /// is read only once, so the completions that arrive during the polling
+    /// are left for the next call.
+    pub fn poll_used(&mut self, max: usize) -> Vec<(u16, u32)> {
+        // read barrier
+        fence(Ordering::SeqCst);
+        let used_idx: u16 = field_ptr!(&self.used, UsedRing, idx).read_once().unwrap();
+
+        let nr_used = (used_idx.wrapping_sub(self.last_used_idx) as usize).min(max);
+        let mut used = Vec::with_capacity(nr_used);
+        for _ in 0..nr_used {
+            let (index, len) = self.last_used_elem();
+            self.recycle_descriptors(index as u16);
+            self.last_used_idx = self.last_used_idx.wrapping_add(1);
+            used.push((index as u16, len));
+        }
+        used
+    }
+
+    /// Reads the used element at the last used index, returning (id, len).
+    fn last_used_elem(&self) -> (u32, u32) {
+        let last_used_slot = self.last_used_idx & (self.queue_size - 1);
+        let element_ptr = {
+            let mut ptr = self.used.borrow_vm();
+            ptr.byte_add(offset_of!(UsedRing, ring) as usize + last_used_slot as usize * 8);
+            ptr.cast::<UsedElem>()
+        };
+        let index = field_ptr!(&element_ptr, UsedElem, id).read_once().unwrap();
+        let len = field_ptr!(&element_ptr, UsedElem, len).read_once().unwrap();
+        (index, len)
+    }
+
     /// Return size of the queue.
     pub fn size(&self) -> u16 {
         self.queue_size
@@ -422,7 +461,7 @@ pub struct Descriptor {
 type DescriptorPtr<'a> = SafePtr<Descriptor, &'a DmaCoherent, TRightSet<TRights![Dup, Write]>>;
 
 #[inline]
-fn set_dma_buf<T: DmaBuf>(desc_ptr: &DescriptorPtr, buf: &T) {
+fn set_dma_buf<T: DmaBuf + ?Sized>(desc_ptr: &DescriptorPtr, buf: &T) {
     // TODO: skip the empty dma buffer or just return error?
     debug_assert_ne!(buf.len(), 0);
     let daddr = buf.daddr();
@@ -487,3 +526,72 @@ bitflags! {
         const VIRTQ_AVAIL_F_NO_INTERRUPT = 1;
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    fn alloc_dma_coherent() -> DmaCoherent {
+        DmaCoherent::map(
+            FrameAllocOptions::new().alloc_segment(1).unwrap().into(),
+            true,
+        )
+        .unwrap()
+    }
+
+    /// Creates a queue that is not attached to any device.
+    fn detached_queue(size: u16) -> VirtQueue {
+        VirtQueue::with_rings(
+            0,
+            size,
+            SafePtr::new(alloc_dma_coherent(), 0),
+            SafePtr::new(alloc_dma_coherent(), 0),
+            SafePtr::new(alloc_dma_coherent(), 0),
+            ConfigManager::new(None, None),
+        )
+    }
+
+    /// Completes the buffer of `token` as the device does.
+    fn complete(queue: &VirtQueue, token: u16, len: u32) {
+        let idx_ptr = field_ptr!(&queue.used, UsedRing, idx);
+        let used_idx: u16 = idx_ptr.read_once().unwrap();
+        let element_ptr = {
+            let mut ptr = queue.used.borrow_vm();
+            ptr.byte_add(
+                offset_of!(UsedRing, ring) as usize
+                    + (used_idx & (queue.queue_size - 1)) as usize * size_of::<UsedElem>(),
+            );
+            ptr.cast::<UsedElem>()
+        };
+        field_ptr!(&element_ptr, UsedElem, id)
+            .write_once(&(token as u32))
+            .unwrap();
+        field_ptr!(&element_ptr, UsedElem, len)
+            .write_once(&len)
+            .unwrap();
+        idx_ptr.write_once(&used_idx.wrapping_add(1)).unwrap();
+    }
+
+    #[ktest]
+    fn poll_used_batch() {
+        let mut queue = detached_queue(8);
+        let buf = alloc_dma_coherent();
+        let tokens: Vec<u16> = (0..3)
+            .map(|_| queue.add_dma_buf(&[&buf], &[&buf]).unwrap())
+            .collect();
+        assert_eq!(queue.available_desc(), 2);
+
+        for (i, token) in tokens.iter().enumerate() {
+            complete(&queue, *token, i as u32 * 100);
+        }
+
+        assert_eq!(queue.poll_used(2), [(tokens[0], 0), (tokens[1], 100)]);
+        assert_eq!(queue.available_desc(), 6);
+        assert_eq!(queue.poll_used(usize::MAX), [(tokens[2], 200)]);
+        assert_eq!(queue.available_desc(), 8);
+        assert!(queue.poll_used(usize::MAX).is_empty());
+        assert!(!queue.can_pop());
+    }
+}
@@ -117,7 +117,7 @@ pub struct ConfigManager<T: Pod> {
 }
 
 impl<T: Pod> ConfigManager<T> {
-    pub(super) fn new(
+    pub(crate) fn new(
         modern_space: Option<SafePtr<T, IoMem>>,
         legacy_space: Option<(Bar, usize)>,
     ) -> Self {
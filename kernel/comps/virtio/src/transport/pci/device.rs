@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 use core::fmt::Debug;
 
 use aster_util::{field_ptr, safe_ptr::SafePtr};
@@ -16,10 +16,15 @@ use ostd::{
     io_mem::IoMem,
     mm::DmaCoherent,
     offset_of,
+    sync::{LocalIrqDisabled, RwLock},
     trap::IrqCallbackFunction,
 };
 
-use super::{common_cfg::VirtioPciCommonCfg, msix::VirtioMsixManager};
+use super::{
+    common_cfg::VirtioPciCommonCfg,
+    isr::{VirtioPciIsr, VirtioPciLegacyIrq},
+    msix::VirtioMsixManager,
+};
 use crate::{
     queue::{AvailRing, Descriptor, UsedRing},
     transport::{
@@ -58,7 +63,14 @@ pub struct VirtioPciModernTransport {
     common_cfg: SafePtr<VirtioPciCommonCfg, IoMem>,
     device_cfg: VirtioPciCapabilityData,
     notify: VirtioPciNotify,
-    msix_manager: VirtioMsixManager,
+    interrupt: VirtioPciInterrupt,
+}
+
+/// The interrupt resources of a virtio-pci device.
+enum VirtioPciInterrupt {
+    Msix(VirtioMsixManager),
+    /// The legacy INTx interrupt, used if MSI-X is not available.
+    Legacy(Arc<RwLock<VirtioPciLegacyIrq, LocalIrqDisabled>>),
 }
 
 impl Debug for VirtioPciModernTransport {
@@ -223,18 +235,31 @@ impl VirtioTransport for VirtioPciModernTransport {
         if index >= self.num_queues() {
             return Err(VirtioTransportError::InvalidArgs);
         }
+        let msix_manager = match &mut self.interrupt {
+            VirtioPciInterrupt::Msix(msix_manager) => msix_manager,
+            VirtioPciInterrupt::Legacy(legacy_irq) => {
+                if single_interrupt {
+                    warn!(
+                        "{:?}: `single_interrupt` ignored: MSI-X is not available",
+                        self.device_type
+                    );
+                }
+                legacy_irq.write().register_queue_callback(func);
+                return Ok(());
+            }
+        };
         let (vector, irq) = if single_interrupt {
-            if let Some(unused_irq) = self.msix_manager.pop_unused_irq() {
+            if let Some(unused_irq) = msix_manager.pop_unused_irq() {
                 unused_irq
             } else {
                 warn!(
                     "{:?}: `single_interrupt` ignored: no more IRQ lines available",
-                    self.device_type()
+                    self.device_type
                 );
-                self.msix_manager.shared_irq_line()
+                msix_manager.shared_irq_line()
             }
         } else {
-            self.msix_manager.shared_irq_line()
+            msix_manager.shared_irq_line()
         };
         irq.on_active(func);
         field_ptr!(&self.common_cfg, VirtioPciCommonCfg, queue_select)
@@ -256,8 +281,15 @@ impl VirtioTransport for VirtioPciModernTransport {
         &mut self,
         func: Box<IrqCallbackFunction>,
     ) -> Result<(), VirtioTransportError> {
-        let (_, irq) = self.msix_manager.config_msix_irq();
-        irq.on_active(func);
+        match &mut self.interrupt {
+            VirtioPciInterrupt::Msix(msix_manager) => {
+                let (_, irq) = msix_manager.config_msix_irq();
+                irq.on_active(func);
+            }
+            VirtioPciInterrupt::Legacy(legacy_irq) => {
+                legacy_irq.write().register_cfg_callback(func);
+            }
+        }
         Ok(())
     }
 
@@ -273,17 +305,9 @@ impl VirtioPciModernTransport {
         common_device: PciCommonDevice,
     ) -> Result<Self, (BusProbeError, PciCommonDevice)> {
         let device_id = common_device.device_id().device_id;
-        if device_id <= 0x1040 {
+        let Some(device_type) = VirtioDeviceType::from_device_id(device_id) else {
             warn!("Unrecognized virtio-pci device id:{:x?}", device_id);
             return Err((BusProbeError::DeviceNotMatch, common_device));
-        }
-
-        let device_type = match VirtioDeviceType::try_from((device_id - 0x1040) as u8) {
-            Ok(device) => device,
-            Err(_) => {
-                warn!("Unrecognized virtio-pci device id:{:x?}", device_id);
-                return Err((BusProbeError::DeviceNotMatch, common_device));
-            }
         };
 
         info!("[Virtio]: Found device:{:?}", device_type);
@@ -292,6 +316,7 @@ impl VirtioPciModernTransport {
         let mut notify = None;
         let mut common_cfg = None;
         let mut device_cfg = None;
+        let mut isr = None;
         for cap in common_device.capabilities().iter() {
             match cap.capability_data() {
                 CapabilityData::Vndr(vendor) => {
@@ -307,7 +332,9 @@ impl VirtioPciModernTransport {
                                 io_memory: data.memory_bar().as_ref().unwrap().io_mem().clone(),
                             });
                         }
-                        VirtioPciCpabilityType::IsrCfg => {}
+                        VirtioPciCpabilityType::IsrCfg => {
+                            isr = VirtioPciIsr::new(&data);
+                        }
                         VirtioPciCpabilityType::DeviceCfg => {
                             device_cfg = Some(data);
                         }
@@ -325,18 +352,35 @@ impl VirtioPciModernTransport {
                 }
             }
         }
-        // TODO: Support interrupt without MSI-X
-        let msix = msix.unwrap();
         let notify = notify.unwrap();
         let common_cfg = common_cfg.unwrap();
         let device_cfg = device_cfg.unwrap();
-        let msix_manager = VirtioMsixManager::new(msix);
+        let interrupt = if let Some(msix) = msix {
+            VirtioPciInterrupt::Msix(VirtioMsixManager::new(msix))
+        } else {
+            let legacy_irq = isr.and_then(|isr| {
+                let irq = common_device.alloc_legacy_irq()?;
+                Some(VirtioPciLegacyIrq::new(irq, isr))
+            });
+            let Some(legacy_irq) = legacy_irq else {
+                warn!(
+                    "{:?}: neither MSI-X nor legacy interrupt is available",
+                    device_type
+                );
+                return Err((BusProbeError::ConfigurationSpaceError, common_device));
+            };
+            info!(
+                "{:?}: MSI-X is not available, using legacy interrupt",
+                device_type
+            );
+            VirtioPciInterrupt::Legacy(legacy_irq)
+        };
         Ok(Self {
             common_device,
             common_cfg,
             device_cfg,
             notify,
-            msix_manager,
+            interrupt,
             device_type,
         })
     }
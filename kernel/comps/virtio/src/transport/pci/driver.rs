@@ -50,6 +50,12 @@ impl PciDriver for VirtioPciDriver {
             return Err((BusProbeError::DeviceNotMatch, device));
         }
 
+        // Legacy devices use an I/O BAR, while modern devices use memory BARs.
+        // Both need DMA for the virtqueues.
+        device.enable_io_space();
+        device.enable_memory_space();
+        device.enable_bus_master();
+
         let device_id = *device.device_id();
         let transport: Box<dyn VirtioTransport> = match device_id.device_id {
             0x1000..0x1040 if (device.device_id().revision_id == 0) => {
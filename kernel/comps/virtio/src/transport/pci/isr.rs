@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::fmt::Debug;
+
+use bitflags::bitflags;
+use ostd::{
+    bus::pci::cfg_space::Bar,
+    sync::{LocalIrqDisabled, RwLock},
+    trap::{IrqCallbackFunction, IrqLine, TrapFrame},
+};
+
+use super::capability::VirtioPciCapabilityData;
+
+bitflags! {
+    /// The ISR status of a virtio-pci device.
+    ///
+    /// Reading the ISR status register also clears it, which acknowledges
+    /// the legacy (INTx) interrupt.
+    pub struct IsrStatus: u8 {
+        /// Used buffer notification of any virtqueue.
+        const QUEUE_INTERRUPT = 1 << 0;
+        /// Configuration change notification.
+        const CONFIG_CHANGE   = 1 << 1;
+    }
+}
+
+/// The ISR status register of a virtio-pci device.
+#[derive(Debug, Clone)]
+pub struct VirtioPciIsr {
+    bar: Bar,
+    offset: usize,
+}
+
+impl VirtioPciIsr {
+    /// Creates the ISR status register from the `VIRTIO_PCI_CAP_ISR_CFG` capability.
+    pub(super) fn new(cap: &VirtioPciCapabilityData) -> Option<Self> {
+        let bar = if let Some(memory_bar) = cap.memory_bar() {
+            Bar::Memory(memory_bar.clone())
+        } else {
+            Bar::Io(cap.io_bar().clone()?)
+        };
+        Some(Self {
+            bar,
+            offset: cap.offset() as usize,
+        })
+    }
+
+    /// Reads and clears the ISR status.
+    pub fn read_status(&self) -> IsrStatus {
+        IsrStatus::from_bits_truncate(self.bar.read_once::<u8>(self.offset).unwrap())
+    }
+}
+
+/// Multiplexing the legacy INTx IRQ of a virtio-pci device.
+///
+/// Without MSI-X, queue interrupts and configuration change interrupts share
+/// the same IRQ, and the ISR status tells them apart.
+pub struct VirtioPciLegacyIrq {
+    irq: IrqLine,
+    isr: VirtioPciIsr,
+    queue_callbacks: Vec<Box<IrqCallbackFunction>>,
+    cfg_callbacks: Vec<Box<IrqCallbackFunction>>,
+}
+
+impl VirtioPciLegacyIrq {
+    pub fn new(mut irq: IrqLine, isr: VirtioPciIsr) -> Arc<RwLock<Self, LocalIrqDisabled>> {
+        Arc::new_cyclic(|weak| {
+            // Holding a weak reference to prevent memory leakage due to
+            // circular reference.
+            let weak = weak.clone();
+            let callback = move |trap_frame: &TrapFrame| {
+                let Some(legacy_irq) = weak.upgrade() else {
+                    return;
+                };
+                let irq = legacy_irq.read();
+                let status = irq.isr.read_status();
+                if status.contains(IsrStatus::QUEUE_INTERRUPT) {
+                    for callback in irq.queue_callbacks.iter() {
+                        callback.call((trap_frame,));
+                    }
+                }
+                if status.contains(IsrStatus::CONFIG_CHANGE) {
+                    for callback in irq.cfg_callbacks.iter() {
+                        callback.call((trap_frame,));
+                    }
+                }
+            };
+            // The IRQ handler takes the lock, which is why the lock disables
+            // local IRQs and is never held while registering the handler.
+            irq.on_active(callback);
+            RwLock::new(Self {
+                irq,
+                isr,
+                queue_callbacks: Vec::new(),
+                cfg_callbacks: Vec::new(),
+            })
+        })
+    }
+
+    pub fn register_queue_callback(&mut self, func: Box<IrqCallbackFunction>) {
+        self.queue_callbacks.push(func);
+    }
+
+    pub fn register_cfg_callback(&mut self, func: Box<IrqCallbackFunction>) {
+        self.cfg_callbacks.push(func);
+    }
+}
+
+impl Debug for VirtioPciLegacyIrq {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VirtioPciLegacyIrq")
+            .field("irq", &self.irq)
+            .field("isr", &self.isr)
+            .finish()
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn isr_status_decoding() {
+        assert_eq!(IsrStatus::from_bits_truncate(0), IsrStatus::empty());
+        assert_eq!(
+            IsrStatus::from_bits_truncate(0b01),
+            IsrStatus::QUEUE_INTERRUPT
+        );
+        assert_eq!(
+            IsrStatus::from_bits_truncate(0b10),
+            IsrStatus::CONFIG_CHANGE
+        );
+        assert_eq!(
+            IsrStatus::from_bits_truncate(0b11),
+            IsrStatus::QUEUE_INTERRUPT | IsrStatus::CONFIG_CHANGE
+        );
+        // Reserved bits are ignored.
+        assert_eq!(
+            IsrStatus::from_bits_truncate(0xfd),
+            IsrStatus::QUEUE_INTERRUPT
+        );
+    }
+}
@@ -76,21 +76,10 @@ impl VirtioPciLegacyTransport {
     pub(super) fn new(
         common_device: PciCommonDevice,
     ) -> Result<Self, (BusProbeError, PciCommonDevice)> {
-        let device_type = match common_device.device_id().device_id {
-            0x1000 => VirtioDeviceType::Network,
-            0x1001 => VirtioDeviceType::Block,
-            0x1002 => VirtioDeviceType::TraditionalMemoryBalloon,
-            0x1003 => VirtioDeviceType::Console,
-            0x1004 => VirtioDeviceType::ScsiHost,
-            0x1005 => VirtioDeviceType::Entropy,
-            0x1009 => VirtioDeviceType::Transport9P,
-            _ => {
-                warn!(
-                    "Unrecognized virtio-pci device id:{:x?}",
-                    common_device.device_id().device_id
-                );
-                return Err((BusProbeError::ConfigurationSpaceError, common_device));
-            }
+        let device_id = common_device.device_id().device_id;
+        let Some(device_type) = VirtioDeviceType::from_device_id(device_id) else {
+            warn!("Unrecognized virtio-pci device id:{:x?}", device_id);
+            return Err((BusProbeError::ConfigurationSpaceError, common_device));
         };
         info!("[Virtio]: Found device:{:?}", device_type);
 
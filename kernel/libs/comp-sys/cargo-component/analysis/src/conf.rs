@@ -13,6 +13,15 @@ pub static CONFIG: OnceCell<Config> = OnceCell::new();
 pub struct Config {
     components: BTreeMap<String, ComponentName>,
     whitelists: BTreeMap<Ident, WhiteList>,
+    severity: Severity,
+}
+
+/// The severity of the diagnostics emitted for disallowed accesses.
+/// `Warn` only reports warnings, while `Deny` reports errors and fails the build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warn,
+    Deny,
 }
 
 impl Config {
@@ -25,12 +34,21 @@ impl Config {
             .get("whitelist")
             .expect("The `whitelist` key does not exist");
         let whitelists = parse_whitelists(whitelist_value);
+        let severity = config_toml
+            .get("severity")
+            .map(parse_severity)
+            .unwrap_or(Severity::Warn);
         Config {
             components,
             whitelists,
+            severity,
         }
     }
 
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
     pub fn ident_full_path(&self, ident: &Ident) -> Path {
         let component_ident = ident.iter().nth(0).unwrap();
         let component_path = self
@@ -198,6 +216,14 @@ fn parse_components(components_value: &Value) -> BTreeMap<String, Path> {
     }
 }
 
+fn parse_severity(severity_value: &Value) -> Severity {
+    match severity_value.as_str() {
+        Some("warn") => Severity::Warn,
+        Some("deny") => Severity::Deny,
+        _ => panic!("`severity` should be either \"warn\" or \"deny\""),
+    }
+}
+
 fn parse_whitelists(whitelist_value: &Value) -> BTreeMap<Ident, WhiteList> {
     let mut recorded_path = Vec::new();
     let mut whitelists = BTreeMap::new();
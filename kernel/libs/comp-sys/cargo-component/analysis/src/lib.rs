@@ -17,8 +17,9 @@ use std::collections::HashSet;
 
 pub use conf::init as init_conf;
 pub use conf::lookup_conf_file;
+use conf::Severity;
 
-use rustc_ast::AttrKind;
+use rustc_ast::{AttrKind, Attribute, LitKind};
 use rustc_middle::mir::{
     Constant, InlineAsmOperand, LocalDecl, Operand, Rvalue, Statement, StatementKind, Terminator,
     TerminatorKind,
@@ -30,6 +31,7 @@ use rustc_span::Span;
 
 const TOOL_NAME: &'static str = "component_access_control";
 const CONTROLLED_ATTR: &'static str = "controlled";
+const ALLOW_ATTR: &'static str = "allow";
 
 pub fn enter_analysis<'tcx>(tcx: TyCtxt<'tcx>) {
     for mir_key in tcx.mir_keys(()) {
@@ -42,6 +44,8 @@ fn check_body_mir(mir_key: LocalDefId, tcx: TyCtxt<'_>) {
     // For const function/block, instance_mir returns mir_for_ctfe.
     // For normal function, instance_mir returns optimized_mir.
     let body = tcx.instance_mir(InstanceDef::Item(def_id));
+    let allowed_paths = allowed_paths_for_body(mir_key, tcx);
+    let severity = conf::CONFIG.get().unwrap().severity();
 
     let mut checked_def_ids = HashSet::new();
     for basic_block_data in body.basic_blocks.iter() {
@@ -49,11 +53,23 @@ fn check_body_mir(mir_key: LocalDefId, tcx: TyCtxt<'_>) {
         // static variables or functions can be found in Operand.
         // FIXME: is this true?
         for statement in &basic_block_data.statements {
-            check_statement(statement, tcx, &mut checked_def_ids);
+            check_statement(
+                statement,
+                tcx,
+                &allowed_paths,
+                severity,
+                &mut checked_def_ids,
+            );
         }
 
         if let Some(terminator) = &basic_block_data.terminator {
-            check_terminator(terminator, tcx, &mut checked_def_ids);
+            check_terminator(
+                terminator,
+                tcx,
+                &allowed_paths,
+                severity,
+                &mut checked_def_ids,
+            );
         }
     }
 
@@ -61,11 +77,48 @@ fn check_body_mir(mir_key: LocalDefId, tcx: TyCtxt<'_>) {
     // while a local decl with type of the function exist. So we further check each local decl to
     // avoid missing any entry points.
     for local_decl in body.local_decls.iter() {
-        check_local_decl(local_decl, tcx, &checked_def_ids)
+        check_local_decl(local_decl, tcx, &allowed_paths, severity, &checked_def_ids)
     }
 }
 
-fn check_statement(statement: &Statement, tcx: TyCtxt<'_>, checked_def_ids: &mut HashSet<DefId>) {
+/// Collect the paths allowed by #[component_access_control::allow("some::path")]
+/// on the function enclosing the body. For closures, the enclosing function is the
+/// function where the closure is defined.
+fn allowed_paths_for_body(mir_key: LocalDefId, tcx: TyCtxt<'_>) -> Vec<conf::Path> {
+    let fn_def_id = tcx.typeck_root_def_id(mir_key.to_def_id());
+    let mut allowed_paths = Vec::new();
+    for attr in tcx.get_attrs_unchecked(fn_def_id) {
+        if !is_tool_attr(attr, ALLOW_ATTR) {
+            continue;
+        }
+        let nested_items = attr
+            .meta_item_list()
+            .expect("The allow attribute should be like `allow(\"some::path\")`");
+        for nested_item in nested_items {
+            let path = nested_item.lit().and_then(|lit| match lit.kind {
+                LitKind::Str(symbol, _) => Some(symbol),
+                _ => None,
+            });
+            let Some(path) = path else {
+                tcx.sess.span_err(
+                    nested_item.span(),
+                    "The allowed path should be a string literal",
+                );
+                continue;
+            };
+            allowed_paths.push(conf::Path::from_qualified_str(path.as_str()));
+        }
+    }
+    allowed_paths
+}
+
+fn check_statement(
+    statement: &Statement,
+    tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
+    severity: Severity,
+    checked_def_ids: &mut HashSet<DefId>,
+) {
     // FIXME: operand only exist in assign statement?
     let mut def_paths = Vec::new();
     if let StatementKind::Assign(assignment) = &statement.kind {
@@ -76,15 +129,33 @@ fn check_statement(statement: &Statement, tcx: TyCtxt<'_>, checked_def_ids: &mut
             | Rvalue::Cast(_, operand, _)
             | Rvalue::UnaryOp(_, operand)
             | Rvalue::ShallowInitBox(operand, _) => {
-                check_invalid_operand(operand, tcx, &mut def_paths, checked_def_ids);
+                check_invalid_operand(operand, tcx, allowed_paths, &mut def_paths, checked_def_ids);
             }
             Rvalue::BinaryOp(_, two_operands) | Rvalue::CheckedBinaryOp(_, two_operands) => {
-                check_invalid_operand(&two_operands.0, tcx, &mut def_paths, checked_def_ids);
-                check_invalid_operand(&two_operands.1, tcx, &mut def_paths, checked_def_ids);
+                check_invalid_operand(
+                    &two_operands.0,
+                    tcx,
+                    allowed_paths,
+                    &mut def_paths,
+                    checked_def_ids,
+                );
+                check_invalid_operand(
+                    &two_operands.1,
+                    tcx,
+                    allowed_paths,
+                    &mut def_paths,
+                    checked_def_ids,
+                );
             }
             Rvalue::Aggregate(_, operands) => {
                 for operand in operands {
-                    check_invalid_operand(operand, tcx, &mut def_paths, checked_def_ids);
+                    check_invalid_operand(
+                        operand,
+                        tcx,
+                        allowed_paths,
+                        &mut def_paths,
+                        checked_def_ids,
+                    );
                 }
             }
             _ => {}
@@ -92,12 +163,20 @@ fn check_statement(statement: &Statement, tcx: TyCtxt<'_>, checked_def_ids: &mut
     }
     let crate_symbol = tcx.crate_name(LOCAL_CRATE);
     let crate_name = crate_symbol.as_str();
-    emit_note(tcx, statement.source_info.span, crate_name, def_paths)
+    emit_note(
+        tcx,
+        statement.source_info.span,
+        crate_name,
+        def_paths,
+        severity,
+    )
 }
 
 fn check_terminator(
     terminator: &Terminator,
     tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
+    severity: Severity,
     checked_def_ids: &mut HashSet<DefId>,
 ) {
     let mut def_paths = Vec::new();
@@ -106,27 +185,45 @@ fn check_terminator(
         | TerminatorKind::DropAndReplace { value: operand, .. }
         | TerminatorKind::Assert { cond: operand, .. }
         | TerminatorKind::Yield { value: operand, .. } => {
-            check_invalid_operand(operand, tcx, &mut def_paths, checked_def_ids);
+            check_invalid_operand(operand, tcx, allowed_paths, &mut def_paths, checked_def_ids);
         }
         TerminatorKind::Call { func, args, .. } => {
-            check_invalid_operand(func, tcx, &mut def_paths, checked_def_ids);
+            check_invalid_operand(func, tcx, allowed_paths, &mut def_paths, checked_def_ids);
             for arg in args {
-                check_invalid_operand(arg, tcx, &mut def_paths, checked_def_ids);
+                check_invalid_operand(arg, tcx, allowed_paths, &mut def_paths, checked_def_ids);
             }
         }
         TerminatorKind::InlineAsm { operands, .. } => {
             for asm_operand in operands {
-                check_inline_asm_operand(&asm_operand, tcx, &mut def_paths, checked_def_ids);
+                check_inline_asm_operand(
+                    &asm_operand,
+                    tcx,
+                    allowed_paths,
+                    &mut def_paths,
+                    checked_def_ids,
+                );
             }
         }
         _ => {}
     }
     let crate_symbol = tcx.crate_name(LOCAL_CRATE);
     let crate_name = crate_symbol.as_str();
-    emit_note(tcx, terminator.source_info.span, crate_name, def_paths)
+    emit_note(
+        tcx,
+        terminator.source_info.span,
+        crate_name,
+        def_paths,
+        severity,
+    )
 }
 
-fn check_local_decl(local_decl: &LocalDecl<'_>, tcx: TyCtxt<'_>, checked_def_ids: &HashSet<DefId>) {
+fn check_local_decl(
+    local_decl: &LocalDecl<'_>,
+    tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
+    severity: Severity,
+    checked_def_ids: &HashSet<DefId>,
+) {
     let ty = local_decl.ty;
     let def_id = if let TyKind::FnDef(def_id, ..) = ty.kind() {
         // func def
@@ -139,14 +236,21 @@ fn check_local_decl(local_decl: &LocalDecl<'_>, tcx: TyCtxt<'_>, checked_def_ids
     }
     let crate_symbol = tcx.crate_name(LOCAL_CRATE);
     let crate_name = crate_symbol.as_str();
-    if let Some(def_path) = def_path_if_invalid_access(def_id, tcx) {
-        emit_note(tcx, local_decl.source_info.span, crate_name, vec![def_path]);
+    if let Some(def_path) = def_path_if_invalid_access(def_id, tcx, allowed_paths) {
+        emit_note(
+            tcx,
+            local_decl.source_info.span,
+            crate_name,
+            vec![def_path],
+            severity,
+        );
     }
 }
 
 fn check_inline_asm_operand(
     asm_operand: &InlineAsmOperand<'_>,
     tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
     def_paths: &mut Vec<String>,
     checked_def_ids: &mut HashSet<DefId>,
 ) {
@@ -155,10 +259,10 @@ fn check_inline_asm_operand(
         | InlineAsmOperand::InOut {
             in_value: operand, ..
         } => {
-            check_invalid_operand(operand, tcx, def_paths, checked_def_ids);
+            check_invalid_operand(operand, tcx, allowed_paths, def_paths, checked_def_ids);
         }
         InlineAsmOperand::Const { value } | InlineAsmOperand::SymFn { value } => {
-            check_constant(value, tcx, def_paths, checked_def_ids);
+            check_constant(value, tcx, allowed_paths, def_paths, checked_def_ids);
         }
         _ => {}
     }
@@ -170,15 +274,17 @@ fn check_inline_asm_operand(
 /// 1. The operand represents a static variable or a func(the first argument can not be self or its variants).
 /// 2. The operand is not defined in local crate.
 /// 3. The operand is marked with #[component_access_control::controlled]
-/// 4. Local crate is not in the whitelist to visit the operand.
+/// 4. Local crate is not in the whitelist to visit the operand, and the enclosing function
+///    is not marked with #[component_access_control::allow] for the operand.
 fn check_invalid_operand(
     operand: &Operand,
     tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
     def_paths: &mut Vec<String>,
     checked_def_ids: &mut HashSet<DefId>,
 ) {
     if let Operand::Constant(constant) = operand {
-        check_constant(&constant, tcx, def_paths, checked_def_ids);
+        check_constant(&constant, tcx, allowed_paths, def_paths, checked_def_ids);
     } else {
         return;
     };
@@ -187,6 +293,7 @@ fn check_invalid_operand(
 fn check_constant(
     constant: &Constant<'_>,
     tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
     def_paths: &mut Vec<String>,
     checked_def_ids: &mut HashSet<DefId>,
 ) {
@@ -205,27 +312,38 @@ fn check_constant(
     };
     checked_def_ids.insert(def_id);
 
-    if let Some(def_path) = def_path_if_invalid_access(def_id, tcx) {
+    if let Some(def_path) = def_path_if_invalid_access(def_id, tcx, allowed_paths) {
         def_paths.push(def_path);
     }
 }
 
-fn def_path_if_invalid_access(def_id: DefId, tcx: TyCtxt<'_>) -> Option<String> {
+fn def_path_if_invalid_access(
+    def_id: DefId,
+    tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
+) -> Option<String> {
     if def_id.is_local() {
         return None;
     }
     if !contains_controlled_attr(def_id, tcx) {
         return None;
     }
-    def_path_if_not_in_whitelist(def_id, tcx)
+    def_path_if_not_in_whitelist(def_id, tcx, allowed_paths)
 }
 
-/// check whether the def_id is in white list.
+/// check whether the def_id is in white list or in the allowed paths of the enclosing function.
 /// If the def_id is **NOT** in white list, return the def_path
-fn def_path_if_not_in_whitelist(def_id: DefId, tcx: TyCtxt<'_>) -> Option<String> {
+fn def_path_if_not_in_whitelist(
+    def_id: DefId,
+    tcx: TyCtxt<'_>,
+    allowed_paths: &[conf::Path],
+) -> Option<String> {
     let crate_symbol = tcx.crate_name(LOCAL_CRATE);
     let crate_name = crate_symbol.as_str();
     let def_path_str = def_path_for_def_id(tcx, def_id);
+    if allowed_paths.contains(&conf::Path::from_qualified_str(&def_path_str)) {
+        return None;
+    }
     if conf::CONFIG
         .get()
         .unwrap()
@@ -241,15 +359,10 @@ fn def_path_if_not_in_whitelist(def_id: DefId, tcx: TyCtxt<'_>) -> Option<String
 fn contains_controlled_attr(def_id: DefId, tcx: TyCtxt<'_>) -> bool {
     for attr in tcx.get_attrs_unchecked(def_id) {
         if let AttrKind::Normal(normal_attr) = &attr.kind {
-            let path_segments = &normal_attr.item.path.segments;
-            if path_segments.len() != 2 {
+            if normal_attr.item.path.segments.len() != 2 {
                 return false;
             }
-            let segment_strs: Vec<_> = path_segments
-                .iter()
-                .map(|segment| segment.ident.as_str())
-                .collect();
-            if segment_strs[0] == TOOL_NAME && segment_strs[1] == CONTROLLED_ATTR {
+            if is_tool_attr(attr, CONTROLLED_ATTR) {
                 return true;
             }
         }
@@ -257,6 +370,22 @@ fn contains_controlled_attr(def_id: DefId, tcx: TyCtxt<'_>) -> bool {
     false
 }
 
+/// if the attribute is component_access_control::attr_name, return true, else return false
+fn is_tool_attr(attr: &Attribute, attr_name: &str) -> bool {
+    if let AttrKind::Normal(normal_attr) = &attr.kind {
+        let path_segments = &normal_attr.item.path.segments;
+        if path_segments.len() != 2 {
+            return false;
+        }
+        let segment_strs: Vec<_> = path_segments
+            .iter()
+            .map(|segment| segment.ident.as_str())
+            .collect();
+        return segment_strs[0] == TOOL_NAME && segment_strs[1] == attr_name;
+    }
+    false
+}
+
 fn def_path_for_def_id(tcx: TyCtxt<'_>, def_id: DefId) -> String {
     match tcx.impl_of_method(def_id) {
         None => common_def_path_str(tcx, def_id),
@@ -285,14 +414,25 @@ fn def_path_str_for_impl(tcx: TyCtxt<'_>, def_id: DefId, impl_def_id: DefId) ->
     unreachable!()
 }
 
-fn emit_note(tcx: TyCtxt<'_>, span: Span, crate_name: &str, def_paths: Vec<String>) {
+fn emit_note(
+    tcx: TyCtxt<'_>,
+    span: Span,
+    crate_name: &str,
+    def_paths: Vec<String>,
+    severity: Severity,
+) {
     if def_paths.len() > 0 {
         let sess = tcx.sess;
         const TITLE: &'static str = "access controlled entry point is disallowed";
         let def_path = def_paths.join(", ");
-        let warning_message = format!("access {} in {}", def_path, crate_name);
-        sess.struct_span_warn(span, TITLE)
-            .note(warning_message)
-            .emit();
+        let note_message = format!("access {} in {}", def_path, crate_name);
+        match severity {
+            Severity::Warn => {
+                sess.struct_span_warn(span, TITLE).note(note_message).emit();
+            }
+            Severity::Deny => {
+                sess.struct_span_err(span, TITLE).note(note_message).emit();
+            }
+        }
     }
 }
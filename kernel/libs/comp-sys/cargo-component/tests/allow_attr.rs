@@ -0,0 +1,16 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// Copyright (C) 2023-2024 Ant Group.
+
+//! This test checks that visiting a controlled resource is allowed in a function marked with
+//! #[component_access_control::allow] for the resource, and only in that function.
+
+#![feature(once_cell)]
+
+mod test_utils;
+
+#[test]
+fn allow_attr() {
+    let stderr = run_cargo_component_cmd!();
+    assert_eq!(stderr.matches("access foo4::foo_add in bar4").count(), 1);
+    assert!(stderr.contains("access foo4::FOO_ITEM in bar4"));
+}
@@ -0,0 +1,16 @@
+#![feature(register_tool)]
+#![register_tool(component_access_control)]
+
+#[component_access_control::allow("foo4::foo_add")]
+pub fn allowed_add(left: usize, right: usize) -> usize {
+    foo4::foo_add(left, right)
+}
+
+pub fn disallowed_add(left: usize, right: usize) -> usize {
+    foo4::foo_add(left, right)
+}
+
+#[component_access_control::allow("foo4::foo_add")]
+pub fn disallowed_item() -> usize {
+    foo4::FOO_ITEM
+}
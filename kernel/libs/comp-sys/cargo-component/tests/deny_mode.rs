@@ -0,0 +1,17 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// Copyright (C) 2023-2024 Ant Group.
+
+//! This test checks that if the severity is set to deny in the config, visiting controlled
+//! resource not in whitelist will be reported as an error.
+
+#![feature(once_cell)]
+
+mod test_utils;
+
+#[test]
+fn deny_mode() {
+    let stderr = run_cargo_component_cmd!();
+    assert!(stderr.contains("error: access controlled entry point is disallowed"));
+    assert!(stderr.contains("access foo5::foo_add in bar5"));
+    assert!(stderr.contains("access foo5::FOO_ITEM in bar5"));
+}
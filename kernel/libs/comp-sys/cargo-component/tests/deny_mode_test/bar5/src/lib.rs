@@ -0,0 +1,5 @@
+pub static BAR: &'static usize = &foo5::FOO_ITEM;
+
+pub fn add(left: usize, right: usize) -> usize {
+    foo5::foo_add(left, right)
+}
\ No newline at end of file
@@ -7,7 +7,7 @@ use std::{
 
 use json::JsonValue;
 use proc_macro2::{Group, TokenStream};
-use quote::{ToTokens, TokenStreamExt};
+use quote::{quote, ToTokens, TokenStreamExt};
 
 use crate::COMPONENT_FILE_NAME;
 
@@ -17,6 +17,8 @@ pub struct ComponentInfo {
     /// The absolute path to the component
     path: String,
     priority: u16,
+    /// The names of the components that this component depends on
+    depends_on: Vec<String>,
 }
 
 impl ToTokens for ComponentInfo {
@@ -26,6 +28,9 @@ impl ToTokens for ComponentInfo {
         )
         .unwrap();
         tokens.append(Group::new(proc_macro2::Delimiter::Parenthesis, token));
+
+        let depends_on = &self.depends_on;
+        tokens.extend(quote!(.with_dependencies(&[#(#depends_on),*])));
     }
 }
 
@@ -98,6 +103,10 @@ pub fn component_generate() -> Vec<ComponentInfo> {
                     .unwrap()
                     .to_string(),
                 priority: *mapping.get(&package_name).unwrap(),
+                depends_on: package["dependencies"]
+                    .members()
+                    .map(|depend| depend["name"].as_str().unwrap().to_string())
+                    .collect(),
             }
         };
         components_info.push(component_info)
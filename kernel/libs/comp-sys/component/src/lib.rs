@@ -11,19 +11,28 @@ extern crate alloc;
 
 use alloc::{
     borrow::ToOwned,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::Debug,
+    format,
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
+use core::time::Duration;
 
 pub use component_macro::*;
 pub use inventory::submit;
 use log::{debug, error, info};
+use spin::{Mutex, Once};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ComponentInitError {
     UninitializedDependencies(String),
+    /// The component cannot be initialized yet, e.g., because a dependency is not ready.
+    ///
+    /// The component system calls the initialization function again after the other
+    /// components, for a bounded number of times.
+    Retryable(String),
     Unknown,
 }
 
@@ -51,10 +60,13 @@ impl Debug for ComponentRegistry {
     }
 }
 
+#[derive(Clone)]
 pub struct ComponentInfo {
     name: String,
     path: String,
     priority: u32,
+    /// The names of the components that this component depends on.
+    depends_on: Vec<String>,
     function: Option<&'static (dyn Fn() -> Result<(), ComponentInitError> + Sync)>,
 }
 
@@ -64,9 +76,36 @@ impl ComponentInfo {
             name: name.to_string(),
             path: path.to_string(),
             priority,
+            depends_on: Vec::new(),
             function: None,
         }
     }
+
+    /// Declares the names of the components that this component depends on.
+    pub fn with_dependencies(mut self, depends_on: &[&str]) -> Self {
+        self.depends_on = depends_on.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Returns the name of the component.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the relative path of the component.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the priority of the component. The lower, the earlier it is initialized.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Returns the names of the components that this component depends on.
+    pub fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
 }
 
 impl PartialEq for ComponentInfo {
@@ -95,14 +134,68 @@ impl Debug for ComponentInfo {
             .field("name", &self.name)
             .field("path", &self.path)
             .field("priority", &self.priority)
+            .field("depends_on", &self.depends_on)
             .finish()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ComponentSystemInitError {
     FileNotValid,
     NotIncludeAllComponent(String),
+    /// The components form a dependency cycle, so no init order can satisfy them.
+    ///
+    /// The names of the components are listed in the order of the dependencies,
+    /// i.e., each component depends on the next one and the last one depends on the first one.
+    DependencyCycle(Vec<String>),
+    /// The initialization function of a component failed in [`init_all_strict`].
+    ComponentInitFailed {
+        path: String,
+        source: ComponentInitError,
+    },
+}
+
+/// The time spent initializing each component, keyed by the component path.
+pub type InitReport = Vec<(String, Duration)>;
+
+static CLOCK: Once<fn() -> Duration> = Once::new();
+static LAST_INIT_REPORT: Mutex<InitReport> = Mutex::new(Vec::new());
+static REGISTERED_COMPONENTS: Mutex<Vec<ComponentInfo>> = Mutex::new(Vec::new());
+
+/// Sets the monotonic clock used to measure the time spent initializing each component.
+///
+/// The clock can only be set once; later calls are ignored.
+/// If no clock is set, the component system does not measure the time.
+pub fn set_clock(clock: fn() -> Duration) {
+    CLOCK.call_once(|| clock);
+}
+
+/// Returns the time spent initializing each component during the last [`init_all`]
+/// or [`init_all_strict`], in the order of initialization.
+///
+/// The report is empty if no clock has been set with [`set_clock`].
+pub fn last_init_report() -> InitReport {
+    LAST_INIT_REPORT.lock().clone()
+}
+
+/// Returns the components registered during the last [`init_all`] or [`init_all_strict`],
+/// in the order of initialization.
+///
+/// The list is empty before the component system is initialized.
+pub fn registered_components() -> Vec<ComponentInfo> {
+    REGISTERED_COMPONENTS.lock().clone()
+}
+
+/// Returns the names of the components registered during the last [`init_all`]
+/// or [`init_all_strict`], in the order of initialization.
+///
+/// The order is sorted by the dependencies and the priorities of the components.
+pub fn init_order() -> Vec<String> {
+    REGISTERED_COMPONENTS
+        .lock()
+        .iter()
+        .map(|info| info.name.clone())
+        .collect()
 }
 
 /// Component system initialization. It will collect invoke all functions that are marked by init_component based on dependencies between crates.
@@ -114,9 +207,32 @@ pub enum ComponentSystemInitError {
 /// ```
 ///
 pub fn init_all(components: Vec<ComponentInfo>) -> Result<(), ComponentSystemInitError> {
+    init_all_with(components, false)
+}
+
+/// Component system initialization that stops at the first failed component.
+///
+/// Unlike [`init_all`], which logs the components that fail to initialize and keeps going,
+/// this function returns [`ComponentSystemInitError::ComponentInitFailed`] as soon as
+/// the initialization function of a component returns an error.
+/// The components after the failed one are not initialized.
+pub fn init_all_strict(components: Vec<ComponentInfo>) -> Result<(), ComponentSystemInitError> {
+    init_all_with(components, true)
+}
+
+/// Initializes the components, stopping at the first failed one if `strict` is true.
+fn init_all_with(
+    components: Vec<ComponentInfo>,
+    strict: bool,
+) -> Result<(), ComponentSystemInitError> {
     let components_info = parse_input(components);
-    match_and_call(components_info)?;
-    Ok(())
+    check_dependency_cycles(&components_info)?;
+    let infos = match_registries(components_info)?;
+    *REGISTERED_COMPONENTS.lock() = infos.clone();
+    let mut report = Vec::new();
+    let res = call_init_functions(infos, strict, &mut report);
+    *LAST_INIT_REPORT.lock() = report;
+    res
 }
 
 fn parse_input(components: Vec<ComponentInfo>) -> BTreeMap<String, ComponentInfo> {
@@ -128,10 +244,75 @@ fn parse_input(components: Vec<ComponentInfo>) -> BTreeMap<String, ComponentInfo
     out
 }
 
+/// Checks that the dependencies between the components do not form a cycle.
+///
+/// Dependencies on components that are not in `components` are ignored,
+/// since such components will never be initialized by the component system.
+fn check_dependency_cycles(
+    components: &BTreeMap<String, ComponentInfo>,
+) -> Result<(), ComponentSystemInitError> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Unvisited,
+        Visiting,
+        Visited,
+    }
+
+    let names: BTreeMap<&str, &ComponentInfo> = components
+        .values()
+        .map(|info| (info.name.as_str(), info))
+        .collect();
+    let mut states: BTreeMap<&str, State> =
+        names.keys().map(|name| (*name, State::Unvisited)).collect();
+
+    for root in names.keys() {
+        if states[root] != State::Unvisited {
+            continue;
+        }
+
+        // Do an iterative depth-first search. Each frame on the stack records
+        // a component and the index of its next dependency to visit.
+        let mut stack: Vec<(&str, usize)> = vec![(root, 0)];
+        states.insert(root, State::Visiting);
+        while let Some((name, next_dep)) = stack.last_mut() {
+            let Some(dep) = names[*name].depends_on.get(*next_dep) else {
+                states.insert(name, State::Visited);
+                stack.pop();
+                continue;
+            };
+            *next_dep += 1;
+
+            let Some((dep, _)) = names.get_key_value(dep.as_str()) else {
+                continue;
+            };
+            match states[dep] {
+                State::Unvisited => {
+                    states.insert(dep, State::Visiting);
+                    stack.push((dep, 0));
+                }
+                State::Visiting => {
+                    let start = stack.iter().position(|(name, _)| name == dep).unwrap();
+                    let cycle = stack[start..]
+                        .iter()
+                        .map(|(name, _)| name.to_string())
+                        .collect();
+                    error!("Component dependency cycle: {cycle:?}");
+                    return Err(ComponentSystemInitError::DependencyCycle(cycle));
+                }
+                State::Visited => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Match the ComponentInfo with ComponentRegistry. The key is the relative path of one component
-fn match_and_call(
+///
+/// Returns the matched components sorted by their dependencies and priorities.
+fn match_registries(
     mut components: BTreeMap<String, ComponentInfo>,
-) -> Result<(), ComponentSystemInitError> {
+) -> Result<Vec<ComponentInfo>, ComponentSystemInitError> {
     let mut infos = Vec::new();
     for registry in inventory::iter::<ComponentRegistry> {
         // relative/path/to/comps/pci/src/lib.rs
@@ -167,18 +348,420 @@ fn match_and_call(
         info!("Exists components that are not initialized");
     }
 
-    infos.sort();
+    let infos = sort_by_dependencies(infos);
     debug!("component infos: {infos:?}");
+    Ok(infos)
+}
+
+/// Sorts the components so that each component comes after all its dependencies.
+///
+/// Among the components whose dependencies are all satisfied, the one with the
+/// lowest priority comes first. Dependencies on components that are not in `infos`
+/// are ignored. The dependencies must not form a cycle, which is ensured by
+/// [`check_dependency_cycles`].
+fn sort_by_dependencies(mut infos: Vec<ComponentInfo>) -> Vec<ComponentInfo> {
+    infos.sort();
+
+    let mut sorted: Vec<ComponentInfo> = Vec::with_capacity(infos.len());
+    while !infos.is_empty() {
+        let is_ready = |info: &ComponentInfo| {
+            info.depends_on.iter().all(|dep| {
+                sorted.iter().any(|sorted| &sorted.name == dep)
+                    || !infos.iter().any(|info| &info.name == dep)
+            })
+        };
+        // Since `infos` is sorted by priority, the first ready one has the lowest priority.
+        let next = infos
+            .iter()
+            .position(is_ready)
+            .expect("the component dependencies form a cycle");
+        sorted.push(infos.remove(next));
+    }
+    sorted
+}
+
+/// The maximum number of rounds to retry the components whose initialization
+/// functions return [`ComponentInitError::Retryable`].
+const MAX_RETRY_ROUNDS: usize = 3;
+
+/// Calls the initialization functions of the components in order.
+///
+/// The components that return [`ComponentInitError::Retryable`] are called again
+/// after all the other components, for up to [`MAX_RETRY_ROUNDS`] rounds, until none
+/// of them is retryable or a round makes no progress.
+///
+/// If `strict` is true, stops at the first component that fails to initialize,
+/// which includes a component that is still retryable after all the rounds.
+/// If a clock is set, the time spent by each called function is appended to `report`.
+fn call_init_functions(
+    infos: Vec<ComponentInfo>,
+    strict: bool,
+    report: &mut InitReport,
+) -> Result<(), ComponentSystemInitError> {
     info!("Components initializing...");
 
-    for i in infos {
-        info!("Component initializing:{:?}", i);
-        if let Err(res) = i.function.unwrap().call(()) {
-            error!("Component initialize error:{:?}", res);
-        } else {
-            info!("Component initialize complete");
+    let mut retryable = call_init_functions_once(infos, strict, report)?;
+    for round in 1..=MAX_RETRY_ROUNDS {
+        if retryable.is_empty() {
+            break;
+        }
+        info!("Retrying {} components, round {}", retryable.len(), round);
+        let nr_retryable = retryable.len();
+        let infos = retryable.into_iter().map(|(info, _)| info).collect();
+        retryable = call_init_functions_once(infos, strict, report)?;
+        if retryable.len() == nr_retryable {
+            break;
+        }
+    }
+
+    if !retryable.is_empty() {
+        let stuck: Vec<&str> = retryable.iter().map(|(info, _)| info.path()).collect();
+        error!("Components still retryable after retries:{stuck:?}");
+        if strict {
+            let (info, err) = retryable.swap_remove(0);
+            return Err(ComponentSystemInitError::ComponentInitFailed {
+                path: info.path,
+                source: err,
+            });
         }
     }
     info!("All components initialization completed");
     Ok(())
 }
+
+/// Calls the initialization functions of the components in order, once for each.
+///
+/// A component is not called if one of its dependencies fails to initialize in this
+/// call. If the dependency is retryable, the component is deferred to be retried
+/// after it.
+///
+/// Returns the components that fail with [`ComponentInitError::Retryable`] or are
+/// deferred, along with their errors.
+fn call_init_functions_once(
+    infos: Vec<ComponentInfo>,
+    strict: bool,
+    report: &mut InitReport,
+) -> Result<Vec<(ComponentInfo, ComponentInitError)>, ComponentSystemInitError> {
+    let clock = CLOCK.get();
+    let mut retryable: Vec<(ComponentInfo, ComponentInitError)> = Vec::new();
+    let mut failed: BTreeSet<String> = BTreeSet::new();
+    for i in infos {
+        if let Some(dep) = i.depends_on.iter().find(|dep| failed.contains(*dep)) {
+            error!(
+                "Component initialize skipped:{:?}, dependency {dep} failed",
+                i.path
+            );
+            failed.insert(i.name.clone());
+            continue;
+        }
+        if let Some(dep) = i
+            .depends_on
+            .iter()
+            .find(|dep| retryable.iter().any(|(info, _)| &info.name == *dep))
+        {
+            info!(
+                "Component initialize deferred:{:?}, dependency {dep} is retryable",
+                i.path
+            );
+            let res = ComponentInitError::Retryable(format!("dependency {dep} is retryable"));
+            retryable.push((i, res));
+            continue;
+        }
+
+        info!("Component initializing:{:?}", i);
+        let start = clock.map(|clock| clock());
+        let res = i.function.unwrap().call(());
+        if let (Some(clock), Some(start)) = (clock, start) {
+            report.push((i.path.clone(), clock().saturating_sub(start)));
+        }
+        match res {
+            Ok(()) => info!("Component initialize complete"),
+            Err(res @ ComponentInitError::Retryable(_)) => {
+                info!("Component initialize deferred:{:?}", res);
+                retryable.push((i, res));
+            }
+            Err(res) => {
+                error!("Component initialize error:{:?}", res);
+                if strict {
+                    return Err(ComponentSystemInitError::ComponentInitFailed {
+                        path: i.path,
+                        source: res,
+                    });
+                }
+                failed.insert(i.name);
+            }
+        }
+    }
+    Ok(retryable)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use alloc::boxed::Box;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn component_with(
+        name: &str,
+        priority: u32,
+        function: impl Fn() -> Result<(), ComponentInitError> + Sync + 'static,
+    ) -> ComponentInfo {
+        let mut info = ComponentInfo::new(name, &format!("comps/{name}"), priority);
+        info.function = Some(Box::leak(Box::new(function)));
+        info
+    }
+
+    fn components(infos: Vec<ComponentInfo>) -> BTreeMap<String, ComponentInfo> {
+        parse_input(infos)
+    }
+
+    #[test]
+    fn no_dependency_cycle() {
+        let infos = components(vec![
+            ComponentInfo::new("a", "comps/a", 2).with_dependencies(&["b", "c"]),
+            ComponentInfo::new("b", "comps/b", 1).with_dependencies(&["c"]),
+            ComponentInfo::new("c", "comps/c", 0),
+        ]);
+        assert_eq!(check_dependency_cycles(&infos), Ok(()));
+    }
+
+    #[test]
+    fn ignore_unknown_dependency() {
+        let infos = components(vec![
+            ComponentInfo::new("a", "comps/a", 1).with_dependencies(&["not-a-component"])
+        ]);
+        assert_eq!(check_dependency_cycles(&infos), Ok(()));
+    }
+
+    #[test]
+    fn detect_dependency_cycle() {
+        let infos = components(vec![
+            ComponentInfo::new("a", "comps/a", 0).with_dependencies(&["b"]),
+            ComponentInfo::new("b", "comps/b", 0).with_dependencies(&["c"]),
+            ComponentInfo::new("c", "comps/c", 0).with_dependencies(&["a"]),
+            ComponentInfo::new("d", "comps/d", 0).with_dependencies(&["a"]),
+        ]);
+        assert_eq!(
+            check_dependency_cycles(&infos),
+            Err(ComponentSystemInitError::DependencyCycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn detect_self_dependency() {
+        let infos = components(vec![
+            ComponentInfo::new("a", "comps/a", 0).with_dependencies(&["a"])
+        ]);
+        assert_eq!(
+            check_dependency_cycles(&infos),
+            Err(ComponentSystemInitError::DependencyCycle(vec![
+                "a".to_string()
+            ]))
+        );
+    }
+
+    fn names(infos: &[ComponentInfo]) -> Vec<&str> {
+        infos.iter().map(|info| info.name.as_str()).collect()
+    }
+
+    #[test]
+    fn init_dependencies_first() {
+        // A depends on B, which depends on C, but their priorities say otherwise.
+        let infos = vec![
+            ComponentInfo::new("a", "comps/a", 0).with_dependencies(&["b"]),
+            ComponentInfo::new("b", "comps/b", 1).with_dependencies(&["c"]),
+            ComponentInfo::new("c", "comps/c", 2),
+        ];
+        assert_eq!(names(&sort_by_dependencies(infos)), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn report_init_order() {
+        let infos = vec![
+            ComponentInfo::new("a", "comps/a", 2),
+            ComponentInfo::new("b", "comps/b", 0),
+            ComponentInfo::new("c", "comps/c", 3).with_dependencies(&["d"]),
+            ComponentInfo::new("d", "comps/d", 1),
+        ];
+        *REGISTERED_COMPONENTS.lock() = sort_by_dependencies(infos);
+
+        assert_eq!(init_order(), ["b", "d", "a", "c"]);
+        let registered = registered_components();
+        let priorities: Vec<u32> = registered.iter().map(|info| info.priority()).collect();
+        assert_eq!(priorities, [0, 1, 2, 3]);
+        assert_eq!(registered[3].path(), "comps/c");
+        assert_eq!(registered[3].depends_on(), ["d"]);
+    }
+
+    #[test]
+    fn priority_breaks_ties() {
+        let infos = vec![
+            ComponentInfo::new("a", "comps/a", 3).with_dependencies(&["c"]),
+            ComponentInfo::new("b", "comps/b", 2),
+            ComponentInfo::new("c", "comps/c", 1).with_dependencies(&["not-a-component"]),
+            ComponentInfo::new("d", "comps/d", 0).with_dependencies(&["c"]),
+        ];
+        assert_eq!(names(&sort_by_dependencies(infos)), ["c", "d", "b", "a"]);
+    }
+
+    /// Returns three components where the middle one fails to initialize.
+    fn components_with_failure(nr_calls: &'static AtomicUsize) -> Vec<ComponentInfo> {
+        let init_ok = move || {
+            nr_calls.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        };
+        let init_err = move || {
+            nr_calls.fetch_add(1, Ordering::Relaxed);
+            Err(ComponentInitError::Unknown)
+        };
+        vec![
+            component_with("first", 0, init_ok),
+            component_with("middle", 1, init_err),
+            component_with("last", 2, init_ok),
+        ]
+    }
+
+    #[test]
+    fn strict_init_stops_at_failure() {
+        static NR_CALLS: AtomicUsize = AtomicUsize::new(0);
+        let res = call_init_functions(components_with_failure(&NR_CALLS), true, &mut Vec::new());
+        assert_eq!(
+            res,
+            Err(ComponentSystemInitError::ComponentInitFailed {
+                path: "comps/middle".to_string(),
+                source: ComponentInitError::Unknown,
+            })
+        );
+        assert_eq!(NR_CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn lenient_init_continues_after_failure() {
+        static NR_CALLS: AtomicUsize = AtomicUsize::new(0);
+        let res = call_init_functions(components_with_failure(&NR_CALLS), false, &mut Vec::new());
+        assert_eq!(res, Ok(()));
+        assert_eq!(NR_CALLS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn retry_until_success() {
+        static NR_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static ORDER: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let infos = vec![
+            component_with("flaky", 0, || {
+                ORDER.lock().push("flaky");
+                if NR_CALLS.fetch_add(1, Ordering::Relaxed) == 0 {
+                    return Err(ComponentInitError::Retryable("bus not scanned".to_string()));
+                }
+                Ok(())
+            }),
+            component_with("bus", 1, || {
+                ORDER.lock().push("bus");
+                Ok(())
+            }),
+        ];
+        assert_eq!(call_init_functions(infos, true, &mut Vec::new()), Ok(()));
+        assert_eq!(NR_CALLS.load(Ordering::Relaxed), 2);
+        assert_eq!(*ORDER.lock(), ["flaky", "bus", "flaky"]);
+    }
+
+    #[test]
+    fn retry_dependents_after_dependency() {
+        static NR_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static ORDER: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let infos = vec![
+            component_with("a", 0, || {
+                ORDER.lock().push("a");
+                if NR_CALLS.fetch_add(1, Ordering::Relaxed) == 0 {
+                    return Err(ComponentInitError::Retryable("not ready".to_string()));
+                }
+                Ok(())
+            }),
+            component_with("b", 1, || {
+                ORDER.lock().push("b");
+                Ok(())
+            })
+            .with_dependencies(&["a"]),
+        ];
+        assert_eq!(call_init_functions(infos, true, &mut Vec::new()), Ok(()));
+        assert_eq!(*ORDER.lock(), ["a", "a", "b"]);
+    }
+
+    #[test]
+    fn skip_dependents_of_failed_component() {
+        static ORDER: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let infos = vec![
+            component_with("a", 0, || {
+                ORDER.lock().push("a");
+                Err(ComponentInitError::Unknown)
+            }),
+            component_with("b", 1, || {
+                ORDER.lock().push("b");
+                Ok(())
+            })
+            .with_dependencies(&["a"]),
+            component_with("c", 2, || {
+                ORDER.lock().push("c");
+                Ok(())
+            })
+            .with_dependencies(&["b"]),
+            component_with("d", 3, || {
+                ORDER.lock().push("d");
+                Ok(())
+            }),
+        ];
+        assert_eq!(call_init_functions(infos, false, &mut Vec::new()), Ok(()));
+        assert_eq!(*ORDER.lock(), ["a", "d"]);
+    }
+
+    #[test]
+    fn stop_retrying_without_progress() {
+        static NR_CALLS: AtomicUsize = AtomicUsize::new(0);
+        let infos = vec![component_with("stuck", 0, || {
+            NR_CALLS.fetch_add(1, Ordering::Relaxed);
+            Err(ComponentInitError::Retryable("never ready".to_string()))
+        })];
+        assert_eq!(
+            call_init_functions(infos, true, &mut Vec::new()),
+            Err(ComponentSystemInitError::ComponentInitFailed {
+                path: "comps/stuck".to_string(),
+                source: ComponentInitError::Retryable("never ready".to_string()),
+            })
+        );
+        // The first call and one retry round, which makes no progress.
+        assert_eq!(NR_CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn record_init_duration() {
+        use std::{sync::OnceLock, thread, time::Instant};
+
+        fn clock() -> Duration {
+            static START: OnceLock<Instant> = OnceLock::new();
+            START.get_or_init(Instant::now).elapsed()
+        }
+        set_clock(clock);
+
+        const SLEEP: Duration = Duration::from_millis(20);
+        let infos = vec![
+            component_with("fast", 0, || Ok(())),
+            component_with("slow", 1, || {
+                thread::sleep(SLEEP);
+                Ok(())
+            }),
+        ];
+        let mut report = Vec::new();
+        assert_eq!(call_init_functions(infos, false, &mut report), Ok(()));
+
+        let paths: Vec<&str> = report.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["comps/fast", "comps/slow"]);
+        assert!(report[1].1 >= SLEEP);
+    }
+}
@@ -22,8 +22,10 @@
 extern crate alloc;
 
 use alloc::{
+    collections::BTreeMap,
     string::{String, ToString},
     vec,
+    vec::Vec,
 };
 use core::cmp::min;
 
@@ -51,6 +53,13 @@ mod test;
 pub struct CpioDecoder<R> {
     reader: R,
     is_error: bool,
+    /// The length of the data (including the padding) of the last entry
+    /// that has not been read yet.
+    unread_len: usize,
+    /// The first names of the hard-linked files, keyed by `(ino, dev_maj, dev_min)`.
+    ///
+    /// This is `None` if hard links are not tracked.
+    hardlinks: Option<BTreeMap<(u32, u32, u32), String>>,
 }
 
 impl<R> CpioDecoder<R>
@@ -62,88 +71,159 @@ where
         Self {
             reader,
             is_error: false,
+            unread_len: 0,
+            hardlinks: None,
         }
     }
-}
 
-#[gat]
-impl<R> LendingIterator for CpioDecoder<R>
-where
-    R: Read,
-{
-    type Item<'a> = Result<CpioEntry<'a, R>>;
+    /// Enable the detection of hard links.
+    ///
+    /// In the newc format, hard-linked files share the same inode number and
+    /// the file data only appears in the last link, while the earlier links are
+    /// empty. With the detection enabled, [`CpioEntry::hardlink_target`] returns
+    /// the name of the first entry that shares the inode, so that the loader can
+    /// create a link instead of a file.
+    ///
+    /// The loader must write the data of a link, if any, through the inode of
+    /// the target. Otherwise, the data carried by the last link is lost.
+    pub fn track_hardlinks(mut self) -> Self {
+        self.hardlinks = Some(BTreeMap::new());
+        self
+    }
 
+    /// Create an iterator that only yields the entries of the given file type.
+    ///
+    /// The data of the other entries is skipped.
+    pub fn filter_type(self, file_type: FileType) -> FilterType<R> {
+        FilterType {
+            decoder: self,
+            file_type,
+        }
+    }
+
+    /// Decodes the next entry whose metadata satisfies `filter`.
+    ///
     /// Stops if reaches to the trailer entry or encounters an error.
-    fn next(&mut self) -> Option<Self::Item<'_>> {
+    fn next_entry<F>(&mut self, filter: F) -> Option<Result<CpioEntry<'_, R>>>
+    where
+        F: Fn(&FileMetadata) -> bool,
+    {
         // Stop to iterate entries if encounters an error.
         if self.is_error {
             return None;
         }
 
-        let entry_result = CpioEntry::new(&mut self.reader);
-        match &entry_result {
-            Ok(entry) => {
-                // A correct CPIO buffer must end with a trailer.
-                if entry.is_trailer() {
-                    return None;
+        loop {
+            let header_result = self
+                .skip_unread()
+                .and_then(|_| EntryHeader::new(&mut self.reader));
+            let header = match header_result {
+                Ok(header) => header,
+                Err(err) => {
+                    self.is_error = true;
+                    return Some(Err(err));
                 }
+            };
+            // A correct CPIO buffer must end with a trailer.
+            if header.name == TRAILER_NAME {
+                return None;
             }
-            Err(_) => {
-                self.is_error = true;
+
+            self.unread_len = header.metadata.size() as usize + header.data_padding_len;
+            let hardlink_target = self.hardlinks.as_mut().and_then(|hardlinks| {
+                let metadata = &header.metadata;
+                if metadata.nlink() <= 1 || metadata.file_type() == FileType::Dir {
+                    return None;
+                }
+                let key = (metadata.ino(), metadata.dev_maj(), metadata.dev_min());
+                if let Some(target) = hardlinks.get(&key) {
+                    return Some(target.clone());
+                }
+                hardlinks.insert(key, header.name.clone());
+                None
+            });
+            if filter(&header.metadata) {
+                return Some(Ok(CpioEntry {
+                    metadata: header.metadata,
+                    name: header.name,
+                    hardlink_target,
+                    reader: &mut self.reader,
+                    unread_len: &mut self.unread_len,
+                    data_padding_len: header.data_padding_len,
+                }));
             }
         }
-        Some(entry_result)
+    }
+
+    /// Skips the data of the last entry that has not been read.
+    fn skip_unread(&mut self) -> Result<()> {
+        if self.unread_len == 0 {
+            return Ok(());
+        }
+        let mut buffer = vec![0u8; min(self.unread_len, 0x1000)];
+        while self.unread_len > 0 {
+            let len = min(buffer.len(), self.unread_len);
+            self.reader.read_exact(&mut buffer[..len])?;
+            self.unread_len -= len;
+        }
+        Ok(())
+    }
+}
+
+#[gat]
+impl<R> LendingIterator for CpioDecoder<R>
+where
+    R: Read,
+{
+    type Item<'a> = Result<CpioEntry<'a, R>>;
+
+    /// Stops if reaches to the trailer entry or encounters an error.
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        self.next_entry(|_| true)
+    }
+}
+
+/// A CPIO decoder that only yields the entries of a specific file type.
+///
+/// This is created by [`CpioDecoder::filter_type`].
+pub struct FilterType<R> {
+    decoder: CpioDecoder<R>,
+    file_type: FileType,
+}
+
+#[gat]
+impl<R> LendingIterator for FilterType<R>
+where
+    R: Read,
+{
+    type Item<'a> = Result<CpioEntry<'a, R>>;
+
+    /// Stops if reaches to the trailer entry or encounters an error.
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        let file_type = self.file_type;
+        self.decoder
+            .next_entry(|metadata| metadata.file_type() == file_type)
     }
 }
 
 /// A file entry in the CPIO.
+///
+/// If the data of the entry is not read, it will be skipped
+/// when the decoder advances to the next entry.
 #[derive(Debug)]
 pub struct CpioEntry<'a, R> {
     metadata: FileMetadata,
     name: String,
+    hardlink_target: Option<String>,
     reader: &'a mut R,
+    unread_len: &'a mut usize,
     data_padding_len: usize,
 }
 
-impl<'a, R> CpioEntry<'a, R>
+impl<R> CpioEntry<'_, R>
 where
     R: Read,
 {
-    fn new(reader: &'a mut R) -> Result<Self> {
-        let (metadata, name, data_padding_len) = {
-            let header = Header::new(reader)?;
-            let name = {
-                let name_size = read_hex_bytes_to_u32(&header.name_size)? as usize;
-                let mut name_bytes = vec![0u8; name_size];
-                reader.read_exact(&mut name_bytes)?;
-                let name = core::ffi::CStr::from_bytes_with_nul(&name_bytes)
-                    .map_err(|_| Error::FileNameError)?;
-                name.to_str().map_err(|_| Error::Utf8Error)?.to_string()
-            };
-            let metadata = if name == TRAILER_NAME {
-                Default::default()
-            } else {
-                FileMetadata::new(&header)?
-            };
-            let data_padding_len = {
-                let header_padding_len = align_up_pad(header.len() + name.len() + 1, 4);
-                if header_padding_len > 0 {
-                    let mut pad_buf = vec![0u8; header_padding_len];
-                    reader.read_exact(&mut pad_buf)?;
-                }
-                align_up_pad(metadata.size() as usize, 4)
-            };
-
-            (metadata, name, data_padding_len)
-        };
-        Ok(Self {
-            metadata,
-            name,
-            reader,
-            data_padding_len,
-        })
-    }
-
     /// The metadata of the file.
     pub fn metadata(&self) -> &FileMetadata {
         &self.metadata
@@ -154,6 +234,16 @@ where
         &self.name
     }
 
+    /// The name of the first entry that shares the inode with this one.
+    ///
+    /// The data of this entry, which is not empty if this is the last link,
+    /// belongs to the inode of the target. See [`CpioDecoder::track_hardlinks`].
+    ///
+    /// This is always `None` unless [`CpioDecoder::track_hardlinks`] is enabled.
+    pub fn hardlink_target(&self) -> Option<&str> {
+        self.hardlink_target.as_deref()
+    }
+
     /// Read all data to the writer.
     pub fn read_all<W>(&mut self, mut writer: W) -> Result<()>
     where
@@ -165,21 +255,137 @@ where
         while send_len < data_len {
             let len = min(buffer.len(), data_len - send_len);
             self.reader.read_exact(&mut buffer[..len])?;
+            *self.unread_len -= len;
             writer.write_all(&buffer[..len])?;
             send_len += len;
         }
         if self.data_padding_len > 0 {
             self.reader
                 .read_exact(&mut buffer[..self.data_padding_len])?;
+            *self.unread_len -= self.data_padding_len;
         }
         Ok(())
     }
 
+    /// Read the target path of a symbolic link.
+    ///
+    /// In the newc format, the target path is stored as the data of the entry.
+    /// Returns `Error::FileTypeError` if the entry is not a symbolic link.
+    pub fn read_link_target(&mut self) -> Result<String> {
+        if !self.metadata.is_symlink() {
+            return Err(Error::FileTypeError);
+        }
+        let mut target = Vec::with_capacity(self.metadata.size() as usize);
+        self.read_all(&mut target)?;
+        String::from_utf8(target).map_err(|_| Error::Utf8Error)
+    }
+
+    /// Extract the entry to the sink, calling the sink method for its file type.
+    ///
+    /// The data of regular files and the targets of symbolic links are read
+    /// from the entry. An entry with a [hard link target](Self::hardlink_target)
+    /// is extracted as a hard link to the target.
+    pub fn extract_to(&mut self, sink: &mut dyn CpioSink) -> Result<()> {
+        let metadata = &self.metadata;
+        let mode = metadata.permission_mode();
+        if let Some(target) = self.hardlink_target.clone() {
+            let mut data = Vec::with_capacity(metadata.size() as usize);
+            self.read_all(&mut data)?;
+            return sink.create_hardlink(&self.name, &target, &data);
+        }
+        match metadata.file_type() {
+            FileType::File => {
+                let mut data = Vec::with_capacity(metadata.size() as usize);
+                self.read_all(&mut data)?;
+                sink.create_file(&self.name, mode, &data)
+            }
+            FileType::Dir => sink.create_dir(&self.name, mode),
+            FileType::Link => {
+                let target = self.read_link_target()?;
+                sink.create_symlink(&self.name, &target)
+            }
+            FileType::FiFo | FileType::Char | FileType::Block | FileType::Socket => sink
+                .create_node(
+                    &self.name,
+                    metadata.file_type().as_unix_mode_bits() | mode as u32,
+                    metadata.rdev_maj(),
+                    metadata.rdev_min(),
+                ),
+        }
+    }
+
     pub fn is_trailer(&self) -> bool {
         self.name == TRAILER_NAME
     }
 }
 
+/// The destination of the entries extracted by [`CpioEntry::extract_to`], e.g., a file system.
+pub trait CpioSink {
+    /// Create a regular file with the permission mode and the data.
+    fn create_file(&mut self, name: &str, mode: u16, data: &[u8]) -> Result<()>;
+
+    /// Create a directory with the permission mode.
+    fn create_dir(&mut self, name: &str, mode: u16) -> Result<()>;
+
+    /// Create a symbolic link to the target.
+    fn create_symlink(&mut self, name: &str, target: &str) -> Result<()>;
+
+    /// Create a hard link to the target, which is an earlier extracted entry.
+    ///
+    /// If the `data` is not empty, it is the data of the file shared by the
+    /// links and must be written through the inode of the target.
+    fn create_hardlink(&mut self, name: &str, target: &str, data: &[u8]) -> Result<()>;
+
+    /// Create a special file, i.e., a FIFO, a socket, or a device file.
+    ///
+    /// The `mode` includes both the file type bits and the permission mode.
+    /// The device IDs are only relevant for device files.
+    fn create_node(&mut self, name: &str, mode: u32, rdev_maj: u32, rdev_min: u32) -> Result<()>;
+}
+
+/// The header, the name, and the padding length of an entry.
+struct EntryHeader {
+    metadata: FileMetadata,
+    name: String,
+    data_padding_len: usize,
+}
+
+impl EntryHeader {
+    fn new<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let header = Header::new(reader)?;
+        let name = {
+            let name_size = read_hex_bytes_to_u32(&header.name_size)? as usize;
+            let mut name_bytes = vec![0u8; name_size];
+            reader.read_exact(&mut name_bytes)?;
+            let name = core::ffi::CStr::from_bytes_with_nul(&name_bytes)
+                .map_err(|_| Error::FileNameError)?;
+            name.to_str().map_err(|_| Error::Utf8Error)?.to_string()
+        };
+        let metadata = if name == TRAILER_NAME {
+            Default::default()
+        } else {
+            FileMetadata::new(&header)?
+        };
+        let data_padding_len = {
+            let header_padding_len = align_up_pad(header.len() + name.len() + 1, 4);
+            if header_padding_len > 0 {
+                let mut pad_buf = vec![0u8; header_padding_len];
+                reader.read_exact(&mut pad_buf)?;
+            }
+            align_up_pad(metadata.size() as usize, 4)
+        };
+
+        Ok(Self {
+            metadata,
+            name,
+            data_padding_len,
+        })
+    }
+}
+
 /// The metadata of the file.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct FileMetadata {
@@ -229,6 +435,31 @@ impl FileMetadata {
         self.type_
     }
 
+    /// Whether the file is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.type_ == FileType::Dir
+    }
+
+    /// Whether the file is a regular file.
+    pub fn is_regular_file(&self) -> bool {
+        self.type_ == FileType::File
+    }
+
+    /// Whether the file is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.type_ == FileType::Link
+    }
+
+    /// Whether the file is a character device or a block device.
+    pub fn is_device(&self) -> bool {
+        matches!(self.type_, FileType::Char | FileType::Block)
+    }
+
+    /// Whether the file is a FIFO special file.
+    pub fn is_fifo(&self) -> bool {
+        self.type_ == FileType::FiFo
+    }
+
     /// The file permission mode, e.g., 0o0755.
     pub fn permission_mode(&self) -> u16 {
         self.mode
@@ -300,6 +531,15 @@ pub enum FileType {
     Socket = 0o140000,
 }
 
+impl FileType {
+    /// The file type bits of the mode, i.e., the bits in `0o170000`.
+    ///
+    /// The bits can be combined with the permission mode to get the full mode.
+    pub fn as_unix_mode_bits(self) -> u32 {
+        self as u32
+    }
+}
+
 impl Default for FileType {
     fn default() -> Self {
         Self::File
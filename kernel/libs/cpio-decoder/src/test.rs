@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use std::collections::BTreeMap;
+
 use lending_iterator::LendingIterator;
 
-use super::{error::*, CpioDecoder, FileType};
+use super::{error::*, CpioDecoder, CpioSink, FileMetadata, FileType, TRAILER_NAME};
 
 #[test]
 fn test_decoder() {
@@ -79,3 +81,331 @@ fn test_invalid_buffer() {
     assert!(entry_result.is_err());
     assert!(entry_result.err() == Some(Error::MagicError));
 }
+
+#[test]
+fn test_skip_unread_data() {
+    let buffer = ArchiveBuilder::new()
+        .file("a", 1, b"unread data")
+        .file("b", 2, b"b")
+        .finish();
+    let mut decoder = CpioDecoder::new(buffer.as_slice());
+
+    let entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "a");
+
+    let mut entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "b");
+    let mut data = Vec::new();
+    entry.read_all(&mut data).unwrap();
+    assert_eq!(data, b"b");
+
+    assert!(decoder.next().is_none());
+}
+
+#[test]
+fn test_filter_type() {
+    let buffer = ArchiveBuilder::new()
+        .dir("dir", 1)
+        .file("dir/file1", 2, b"hello")
+        .symlink("dir/link", 3, "file1")
+        .file("dir/file2", 4, b"cpio!!")
+        .finish();
+    let mut decoder = CpioDecoder::new(buffer.as_slice()).filter_type(FileType::File);
+
+    let mut files = Vec::new();
+    while let Some(entry_result) = decoder.next() {
+        let mut entry = entry_result.unwrap();
+        assert_eq!(entry.metadata().file_type(), FileType::File);
+        let mut data = Vec::new();
+        entry.read_all(&mut data).unwrap();
+        files.push((entry.name().to_string(), data));
+    }
+    assert_eq!(
+        files,
+        [
+            ("dir/file1".to_string(), b"hello".to_vec()),
+            ("dir/file2".to_string(), b"cpio!!".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_hardlink() {
+    let buffer = ArchiveBuilder::new()
+        .hardlink("link1", 7, b"")
+        .file("other", 8, b"other")
+        .hardlink("link2", 7, b"shared data")
+        .finish();
+
+    let mut decoder = CpioDecoder::new(buffer.as_slice()).track_hardlinks();
+    let entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "link1");
+    assert_eq!(entry.hardlink_target(), None);
+    let entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "other");
+    assert_eq!(entry.hardlink_target(), None);
+    let mut entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "link2");
+    assert_eq!(entry.hardlink_target(), Some("link1"));
+    let mut data = Vec::new();
+    entry.read_all(&mut data).unwrap();
+    assert_eq!(data, b"shared data");
+    assert!(decoder.next().is_none());
+
+    // Hard links are not detected by default.
+    let mut decoder = CpioDecoder::new(buffer.as_slice());
+    while let Some(entry_result) = decoder.next() {
+        assert_eq!(entry_result.unwrap().hardlink_target(), None);
+    }
+}
+
+#[test]
+fn test_extract_hardlinks() {
+    let buffer = ArchiveBuilder::new()
+        .hardlink("link1", 7, b"")
+        .hardlink("link2", 7, b"shared data")
+        .finish();
+
+    // A loader that writes the data of the links through the inodes of their targets.
+    let mut inodes: Vec<Vec<u8>> = Vec::new();
+    let mut names = BTreeMap::new();
+    let mut decoder = CpioDecoder::new(buffer.as_slice()).track_hardlinks();
+    while let Some(entry_result) = decoder.next() {
+        let mut entry = entry_result.unwrap();
+        let mut data = Vec::new();
+        entry.read_all(&mut data).unwrap();
+        let inode = match entry.hardlink_target() {
+            Some(target) => {
+                let inode = names[target];
+                if !data.is_empty() {
+                    inodes[inode] = data;
+                }
+                inode
+            }
+            None => {
+                inodes.push(data);
+                inodes.len() - 1
+            }
+        };
+        names.insert(entry.name().to_string(), inode);
+    }
+
+    assert_eq!(inodes[names["link1"]], b"shared data");
+    assert_eq!(inodes[names["link2"]], b"shared data");
+}
+
+#[test]
+fn test_read_link_target() {
+    let buffer = ArchiveBuilder::new()
+        .symlink("link", 1, "dir/target")
+        .file("file", 2, b"data")
+        .entry(
+            "bad-link",
+            3,
+            FileType::Link as u32 | 0o777,
+            1,
+            &[0xff, 0xfe],
+        )
+        .finish();
+
+    let mut decoder = CpioDecoder::new(buffer.as_slice());
+    let mut entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.read_link_target(), Ok("dir/target".to_string()));
+    let mut entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.read_link_target(), Err(Error::FileTypeError));
+    let mut entry = decoder.next().unwrap().unwrap();
+    assert_eq!(entry.read_link_target(), Err(Error::Utf8Error));
+    assert!(decoder.next().is_none());
+}
+
+#[test]
+fn test_file_type_helpers() {
+    let all_types = [
+        FileType::FiFo,
+        FileType::Char,
+        FileType::Dir,
+        FileType::Block,
+        FileType::File,
+        FileType::Link,
+        FileType::Socket,
+    ];
+    for type_ in all_types {
+        let metadata = FileMetadata {
+            type_,
+            ..Default::default()
+        };
+        assert_eq!(metadata.is_dir(), type_ == FileType::Dir);
+        assert_eq!(metadata.is_regular_file(), type_ == FileType::File);
+        assert_eq!(metadata.is_symlink(), type_ == FileType::Link);
+        assert_eq!(
+            metadata.is_device(),
+            type_ == FileType::Char || type_ == FileType::Block
+        );
+        assert_eq!(metadata.is_fifo(), type_ == FileType::FiFo);
+
+        let mode_bits = type_.as_unix_mode_bits();
+        assert_eq!(mode_bits & !0o170000, 0);
+        assert_eq!(FileType::try_from(mode_bits).ok(), Some(type_));
+    }
+    assert_eq!(FileType::Dir.as_unix_mode_bits() | 0o755, 0o040755);
+    assert_eq!(FileType::Link.as_unix_mode_bits(), 0o120000);
+}
+
+#[test]
+fn test_extract_to() {
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        File(String, u16, Vec<u8>),
+        Dir(String, u16),
+        Symlink(String, String),
+        Hardlink(String, String, Vec<u8>),
+        Node(String, u32, u32, u32),
+    }
+
+    #[derive(Default)]
+    struct MockSink {
+        calls: Vec<Call>,
+    }
+
+    impl CpioSink for MockSink {
+        fn create_file(&mut self, name: &str, mode: u16, data: &[u8]) -> Result<()> {
+            self.calls
+                .push(Call::File(name.to_string(), mode, data.to_vec()));
+            Ok(())
+        }
+
+        fn create_dir(&mut self, name: &str, mode: u16) -> Result<()> {
+            self.calls.push(Call::Dir(name.to_string(), mode));
+            Ok(())
+        }
+
+        fn create_symlink(&mut self, name: &str, target: &str) -> Result<()> {
+            self.calls
+                .push(Call::Symlink(name.to_string(), target.to_string()));
+            Ok(())
+        }
+
+        fn create_hardlink(&mut self, name: &str, target: &str, data: &[u8]) -> Result<()> {
+            self.calls.push(Call::Hardlink(
+                name.to_string(),
+                target.to_string(),
+                data.to_vec(),
+            ));
+            Ok(())
+        }
+
+        fn create_node(
+            &mut self,
+            name: &str,
+            mode: u32,
+            rdev_maj: u32,
+            rdev_min: u32,
+        ) -> Result<()> {
+            self.calls
+                .push(Call::Node(name.to_string(), mode, rdev_maj, rdev_min));
+            Ok(())
+        }
+    }
+
+    let buffer = ArchiveBuilder::new()
+        .dir("dev", 1)
+        .entry("dev/fifo", 2, FileType::FiFo as u32 | 0o600, 1, b"")
+        .file("init", 3, b"#!/bin/sh")
+        .symlink("sh", 4, "init")
+        .hardlink("link1", 5, b"")
+        .hardlink("link2", 5, b"shared data")
+        .finish();
+
+    let mut sink = MockSink::default();
+    let mut decoder = CpioDecoder::new(buffer.as_slice()).track_hardlinks();
+    while let Some(entry_result) = decoder.next() {
+        entry_result.unwrap().extract_to(&mut sink).unwrap();
+    }
+    assert_eq!(
+        sink.calls,
+        [
+            Call::Dir("dev".to_string(), 0o755),
+            Call::Node("dev/fifo".to_string(), 0o010600, 0, 0),
+            Call::File("init".to_string(), 0o644, b"#!/bin/sh".to_vec()),
+            Call::Symlink("sh".to_string(), "init".to_string()),
+            Call::File("link1".to_string(), 0o644, Vec::new()),
+            Call::Hardlink(
+                "link2".to_string(),
+                "link1".to_string(),
+                b"shared data".to_vec()
+            ),
+        ]
+    );
+}
+
+/// A builder of newc CPIO archives for tests.
+struct ArchiveBuilder {
+    buffer: Vec<u8>,
+}
+
+impl ArchiveBuilder {
+    fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    fn dir(self, name: &str, ino: u32) -> Self {
+        self.entry(name, ino, FileType::Dir as u32 | 0o755, 2, b"")
+    }
+
+    fn file(self, name: &str, ino: u32, data: &[u8]) -> Self {
+        self.entry(name, ino, FileType::File as u32 | 0o644, 1, data)
+    }
+
+    fn hardlink(self, name: &str, ino: u32, data: &[u8]) -> Self {
+        self.entry(name, ino, FileType::File as u32 | 0o644, 2, data)
+    }
+
+    fn symlink(self, name: &str, ino: u32, target: &str) -> Self {
+        self.entry(
+            name,
+            ino,
+            FileType::Link as u32 | 0o777,
+            1,
+            target.as_bytes(),
+        )
+    }
+
+    fn entry(mut self, name: &str, ino: u32, mode: u32, nlink: u32, data: &[u8]) -> Self {
+        let fields = [
+            ino,
+            mode,
+            0, // uid
+            0, // gid
+            nlink,
+            0, // mtime
+            data.len() as u32,
+            0,                     // dev_maj
+            0,                     // dev_min
+            0,                     // rdev_maj
+            0,                     // rdev_min
+            name.len() as u32 + 1, // name_size
+            0,                     // chksum
+        ];
+        self.buffer.extend_from_slice(b"070701");
+        for field in fields {
+            self.buffer
+                .extend_from_slice(format!("{:08x}", field).as_bytes());
+        }
+        self.buffer.extend_from_slice(name.as_bytes());
+        self.buffer.push(0);
+        self.pad();
+        self.buffer.extend_from_slice(data);
+        self.pad();
+        self
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.entry(TRAILER_NAME, 0, 0, 1, b"").buffer
+    }
+
+    fn pad(&mut self) {
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+    }
+}
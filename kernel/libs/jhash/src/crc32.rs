@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module implements the CRC-32 checksum used by IEEE 802.3 (Ethernet), zlib and gzip.
+//!
+//! The checksum is computed byte by byte with a precomputed table.
+
+/// The reversed polynomial of CRC-32 (IEEE 802.3)
+const CRC32_IEEE_POLY: u32 = 0xedb88320;
+
+/// The lookup table, where entry `n` is the CRC of the byte `n`
+const CRC32_IEEE_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_IEEE_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of a u8 slice.
+///
+/// `init` is the checksum of the preceding data, or zero if there is none.
+/// So a checksum can be computed piece by piece.
+///
+/// # Example
+///
+/// ```rust
+/// use jhash::crc32::crc32_ieee;
+///
+/// assert_eq!(crc32_ieee(b"123456789", 0), 0xcbf43926);
+/// assert_eq!(crc32_ieee(b"6789", crc32_ieee(b"12345", 0)), 0xcbf43926);
+/// ```
+pub const fn crc32_ieee(data: &[u8], init: u32) -> u32 {
+    let mut crc = !init;
+    let mut index = 0;
+    while index < data.len() {
+        crc = CRC32_IEEE_TABLE[((crc ^ data[index] as u32) & 0xff) as usize] ^ (crc >> 8);
+        index += 1;
+    }
+    !crc
+}
+
+/// The state of a CRC-32 (IEEE 802.3) checksum that is computed incrementally.
+///
+/// Updating the state with several slices gives the same checksum as
+/// [`crc32_ieee`] of their concatenation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Crc32State {
+    crc: u32,
+}
+
+impl Crc32State {
+    /// Creates a new state with no data.
+    pub const fn new() -> Self {
+        Self { crc: 0 }
+    }
+
+    /// Appends the data to the checksum.
+    pub const fn update(&mut self, data: &[u8]) {
+        self.crc = crc32_ieee(data, self.crc);
+    }
+
+    /// Returns the checksum of all the data appended so far.
+    pub const fn finish(&self) -> u32 {
+        self.crc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crc32_ieee() {
+        assert_eq!(crc32_ieee(b"123456789", 0), 0xcbf43926);
+        assert_eq!(crc32_ieee(b"", 0), 0);
+        assert_eq!(crc32_ieee(b"a", 0), 0xe8b7be43);
+        assert_eq!(
+            crc32_ieee(b"The quick brown fox jumps over the lazy dog", 0),
+            0x414fa339
+        );
+    }
+
+    #[test]
+    fn test_crc32_state() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        for mid in 0..=data.len() {
+            let mut state = Crc32State::new();
+            state.update(&data[..mid]);
+            state.update(&data[mid..]);
+            assert_eq!(state.finish(), crc32_ieee(data, 0));
+        }
+        assert_eq!(Crc32State::new().finish(), 0);
+    }
+}
@@ -8,10 +8,19 @@
 //! Although the [Linux kernel's jhash](https://github.com/torvalds/linux/blob/master/include/linux/jhash.h)
 //! slightly differs from the original version, this module reimplements the Linux kernel's version.
 //!
+//! The [`crc32`] module provides the CRC-32 checksum for the protocols and formats that require it.
+//!
 
 #![no_std]
 #![deny(unsafe_code)]
 
+extern crate alloc;
+
+pub mod crc32;
+
+use alloc::vec::Vec;
+use core::hash::{BuildHasherDefault, Hasher};
+
 /// A randomly chosen initial value
 const JHASH_INITVAL: u32 = 0xdeadbeef;
 
@@ -191,6 +200,58 @@ pub const fn jhash_u32_array(array: &[u32], initval: u32) -> u32 {
     c
 }
 
+/// A [`Hasher`] that hashes all the written bytes with [`jhash_slice`].
+///
+/// `jhash_slice` is not a streaming hash: hashing two slices one after another
+/// does not give the same result as hashing their concatenation. To make the
+/// result independent of how the input is split into `write` calls, the hasher
+/// buffers all the written bytes and hashes them at once in `finish`. So the
+/// result always equals `jhash_slice` of the concatenated input, widened to
+/// `u64`.
+///
+/// # Example
+///
+/// ```rust
+/// use core::hash::{Hash, Hasher};
+///
+/// use jhash::{JHasher, jhash_slice};
+///
+/// let mut hasher = JHasher::default();
+/// hasher.write(b"hello ");
+/// hasher.write(b"world");
+/// assert_eq!(hasher.finish(), jhash_slice(b"hello world", 0) as u64);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct JHasher {
+    bytes: Vec<u8>,
+    initval: u32,
+}
+
+impl JHasher {
+    /// Creates a new [`JHasher`] with the given initial value.
+    pub const fn new(initval: u32) -> Self {
+        Self {
+            bytes: Vec::new(),
+            initval,
+        }
+    }
+}
+
+impl Hasher for JHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        jhash_slice(&self.bytes, self.initval) as u64
+    }
+}
+
+/// A builder of [`JHasher`]s with the initial value of zero.
+///
+/// It can be used as the hasher of hash maps, e.g., `HashMap<K, V, JHasherBuilder>`.
+pub type JHasherBuilder = BuildHasherDefault<JHasher>;
+
 /// An internal function that handles hashing for 3 u32 values
 const fn jhash_3vals_inner(mut a: u32, mut b: u32, mut c: u32, initval: u32) -> u32 {
     a = a.wrapping_add(initval);
@@ -336,4 +397,36 @@ mod test {
         assert_eq!(hash_slices(&test_slices[0..1]), 729031446);
         assert_eq!(hash_slices(&[]), 0);
     }
+
+    #[test]
+    fn test_jhasher() {
+        use alloc::vec::Vec;
+        use core::hash::{BuildHasher, Hash, Hasher};
+
+        #[derive(Hash)]
+        struct Key {
+            id: u32,
+            port: u16,
+        }
+
+        let key = Key { id: 42, port: 8080 };
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&42u32.to_ne_bytes());
+        bytes.extend_from_slice(&8080u16.to_ne_bytes());
+
+        let mut hasher = JHasher::default();
+        key.hash(&mut hasher);
+        assert_eq!(hasher.finish(), jhash_slice(&bytes, 0) as u64);
+        assert_eq!(JHasherBuilder::default().hash_one(&key), hasher.finish());
+
+        let mut hasher = JHasher::new(10);
+        key.hash(&mut hasher);
+        assert_eq!(hasher.finish(), jhash_slice(&bytes, 10) as u64);
+
+        // Streaming the input gives the same result as hashing it at once.
+        let mut hasher = JHasher::default();
+        hasher.write(b"hello ");
+        hasher.write(b"world");
+        assert_eq!(hasher.finish(), 1252609637);
+    }
 }
@@ -76,6 +76,7 @@ pub mod vm;
 #[controlled]
 pub fn main() {
     ostd::early_println!("[kernel] OSTD initialized. Preparing components.");
+    component::set_clock(|| ostd::timer::Jiffies::elapsed().as_duration());
     component::init_all(component::parse_metadata!()).unwrap();
     init();
 
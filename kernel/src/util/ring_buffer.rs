@@ -8,7 +8,7 @@ use core::{
 
 use align_ext::AlignExt;
 use inherit_methods_macro::inherit_methods;
-use ostd::mm::{FrameAllocOptions, Segment, UntypedMem, VmIo};
+use ostd::mm::{FrameAllocOptions, Infallible, Segment, UntypedMem, VmIo};
 
 use super::{MultiRead, MultiWrite};
 use crate::prelude::*;
@@ -57,17 +57,57 @@ pub struct RingBuffer<T> {
 /// A producer of a [`RingBuffer`].
 pub struct Producer<T, R: Deref<Target = RingBuffer<T>>> {
     rb: R,
+    on_nonempty: Option<Box<dyn Fn() + Send + Sync>>,
     phantom: PhantomData<T>,
 }
 /// A consumer of a [`RingBuffer`].
 pub struct Consumer<T, R: Deref<Target = RingBuffer<T>>> {
     rb: R,
+    on_nonfull: Option<Box<dyn Fn() + Send + Sync>>,
     phantom: PhantomData<T>,
 }
 
+/// An iterator that pops items from a [`RingBuffer`] through a [`Consumer`].
+///
+/// This is created by [`Consumer::drain`] and [`Consumer::drain_at_most`].
+pub struct Drain<'a, T, R: Deref<Target = RingBuffer<T>>> {
+    consumer: &'a mut Consumer<T, R>,
+    remaining: usize,
+}
+
+/// A contiguous span of free bytes in a [`RingBuffer`] reserved by a [`Producer`].
+///
+/// This is created by [`Producer::reserve`]. The bytes written to the span
+/// become visible to the consumer only after [`Reservation::commit`]. Dropping
+/// the reservation without committing it discards the written bytes.
+pub struct Reservation<'a, R: Deref<Target = RingBuffer<u8>>> {
+    producer: &'a mut Producer<u8, R>,
+    pos: usize,
+    len: usize,
+}
+
+/// A snapshot of the progress of a [`RingBuffer`].
+///
+/// This is created by [`RingBuffer::snapshot`], which is also available on
+/// [`Producer`] and [`Consumer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RingSnapshot {
+    /// The position of the next item to pop.
+    pub head: usize,
+    /// The position of the next item to push.
+    pub tail: usize,
+    /// The number of items in the ring buffer.
+    pub len: usize,
+    /// The capacity of the ring buffer.
+    pub capacity: usize,
+}
+
 pub type RbProducer<T> = Producer<T, Arc<RingBuffer<T>>>;
 pub type RbConsumer<T> = Consumer<T, Arc<RingBuffer<T>>>;
 
+/// The length of the prefix of a frame written by [`Producer::write_frame`].
+const FRAME_PREFIX_LEN: usize = size_of::<u32>();
+
 impl<T> RingBuffer<T> {
     const T_SIZE: usize = core::mem::size_of::<T>();
 
@@ -82,6 +122,30 @@ impl<T> RingBuffer<T> {
             .zeroed(false)
             .alloc_segment(nframes)
             .unwrap();
+        Self::with_segment(segment, capacity)
+    }
+
+    /// Creates a new [`RingBuffer`] with the given capacity over a pre-allocated segment.
+    ///
+    /// This allows the items to reside in memory with special requirements,
+    /// e.g., memory that is visible to a device.
+    ///
+    /// Returns an error if the capacity is not a power of two or
+    /// the segment cannot hold `capacity` items.
+    pub fn from_segment(segment: Segment<()>, capacity: usize) -> Result<Self> {
+        if !capacity.is_power_of_two() {
+            return_errno_with_message!(Errno::EINVAL, "capacity must be a power of two");
+        }
+        if capacity
+            .checked_mul(Self::T_SIZE)
+            .is_none_or(|size| size > segment.size())
+        {
+            return_errno_with_message!(Errno::EINVAL, "the segment is too small for the capacity");
+        }
+        Ok(Self::with_segment(segment, capacity))
+    }
+
+    fn with_segment(segment: Segment<()>, capacity: usize) -> Self {
         Self {
             segment,
             capacity,
@@ -94,14 +158,8 @@ impl<T> RingBuffer<T> {
 
     /// Splits the [`RingBuffer`] into a producer and a consumer.
     pub fn split(self) -> (RbProducer<T>, RbConsumer<T>) {
-        let producer = Producer {
-            rb: Arc::new(self),
-            phantom: PhantomData,
-        };
-        let consumer = Consumer {
-            rb: Arc::clone(&producer.rb),
-            phantom: PhantomData,
-        };
+        let producer = Producer::new(Arc::new(self));
+        let consumer = Consumer::new(Arc::clone(&producer.rb));
         (producer, consumer)
     }
 
@@ -129,6 +187,24 @@ impl<T> RingBuffer<T> {
     fn free_len(&self) -> usize {
         self.capacity - self.len()
     }
+
+    /// Takes a snapshot of the progress of the `RingBuffer`.
+    ///
+    /// This is meant for debugging, e.g., to find out which side of a stalled
+    /// ring buffer stops making progress. The fields are loaded one after
+    /// another without locking, so they are only guaranteed to agree with
+    /// each other if neither the producer nor the consumer is running.
+    pub fn snapshot(&self) -> RingSnapshot {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        let len = self.len();
+        RingSnapshot {
+            head,
+            tail,
+            len,
+            capacity: self.capacity,
+        }
+    }
 }
 
 impl<T: Pod> RingBuffer<T> {
@@ -137,10 +213,7 @@ impl<T: Pod> RingBuffer<T> {
     /// Returns `Some` on success. Returns `None` if
     /// the ring buffer is full.
     pub fn push(&mut self, item: T) -> Option<()> {
-        let mut producer = Producer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut producer = Producer::new(self);
         producer.push(item)
     }
 
@@ -149,10 +222,7 @@ impl<T: Pod> RingBuffer<T> {
     /// Returns `Some` on success, all items are pushed to the ring buffer.
     /// Returns `None` if the ring buffer is full or cannot fit all items.
     pub fn push_slice(&mut self, items: &[T]) -> Option<()> {
-        let mut producer = Producer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut producer = Producer::new(self);
         producer.push_slice(items)
     }
 
@@ -161,9 +231,8 @@ impl<T: Pod> RingBuffer<T> {
     ///
     /// Returns the overwritten item if any.
     pub fn push_overwrite(&mut self, item: T) -> Option<T> {
-        let ret = if self.is_full() { self.pop() } else { None };
-        self.push(item).unwrap();
-        ret
+        let mut producer = Producer::new(self);
+        producer.push_overwrite(item)
     }
 
     /// Pops an item from the `RingBuffer`.
@@ -171,10 +240,7 @@ impl<T: Pod> RingBuffer<T> {
     /// Returns `Some` with the popped item on success.
     /// Returns `None` if the ring buffer is empty.
     pub fn pop(&mut self) -> Option<T> {
-        let mut consumer = Consumer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut consumer = Consumer::new(self);
         consumer.pop()
     }
 
@@ -183,13 +249,25 @@ impl<T: Pod> RingBuffer<T> {
     /// Returns `Some` on success, all items are popped from the ring buffer.
     /// Returns `None` if the ring buffer is empty or cannot fill all items.
     pub fn pop_slice(&mut self, items: &mut [T]) -> Option<()> {
-        let mut consumer = Consumer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut consumer = Consumer::new(self);
         consumer.pop_slice(items)
     }
 
+    /// Returns an iterator over the items in the `RingBuffer` without popping them.
+    ///
+    /// The items are yielded from the oldest to the newest. Only the items
+    /// present at the time of the call are yielded. This is meant for
+    /// inspection, e.g., dumping the in-flight items for debugging.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let head = self.head();
+        let len = self.len();
+        (0..len).map(move |i| {
+            let pos = (head + i) & (self.capacity - 1);
+            let mut reader = self.segment.reader().skip(pos * Self::T_SIZE);
+            reader.read_val::<T>().unwrap()
+        })
+    }
+
     /// Clears the `RingBuffer`.
     pub fn clear(&mut self) {
         self.tail.store(0, Ordering::Relaxed);
@@ -205,18 +283,27 @@ impl<T: Pod> RingBuffer<T> {
         self.head.load(Ordering::Acquire)
     }
 
-    fn advance_tail(&self, curr_pos: usize, len: usize) {
+    /// Advances the tail and returns the number of items before advancing.
+    fn advance_tail(&self, curr_pos: usize, len: usize) -> usize {
         let next_pos = (curr_pos + len) & (self.capacity - 1);
         self.tail.store(next_pos, Ordering::Release);
 
-        self.len.fetch_add(len, Ordering::Release);
+        let prev_len = self.len.fetch_add(len, Ordering::Release);
+        debug_assert!(
+            prev_len + len <= self.capacity,
+            "the tail overtakes the head"
+        );
+        prev_len
     }
 
-    fn advance_head(&self, curr_pos: usize, len: usize) {
+    /// Advances the head and returns the number of items before advancing.
+    fn advance_head(&self, curr_pos: usize, len: usize) -> usize {
         let next_pos = (curr_pos + len) & (self.capacity - 1);
         self.head.store(next_pos, Ordering::Release);
 
-        self.len.fetch_sub(len, Ordering::Release);
+        let prev_len = self.len.fetch_sub(len, Ordering::Release);
+        debug_assert!(len <= prev_len, "the head overtakes the tail");
+        prev_len
     }
 }
 
@@ -226,10 +313,7 @@ impl RingBuffer<u8> {
     /// Returns the number of bytes written.
     #[allow(unused)]
     pub fn write_fallible(&mut self, reader: &mut dyn MultiRead) -> Result<usize> {
-        let mut producer = Producer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut producer = Producer::new(self);
         producer.write_fallible(reader)
     }
 
@@ -237,10 +321,7 @@ impl RingBuffer<u8> {
     ///
     /// Returns the number of bytes read.
     pub fn read_fallible(&mut self, writer: &mut dyn MultiWrite) -> Result<usize> {
-        let mut consumer = Consumer {
-            rb: self,
-            phantom: PhantomData,
-        };
+        let mut consumer = Consumer::new(self);
         consumer.read_fallible(writer)
     }
 }
@@ -248,6 +329,15 @@ impl RingBuffer<u8> {
 impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
     const T_SIZE: usize = core::mem::size_of::<T>();
 
+    fn commit_push(&self, tail: usize, nitems: usize) {
+        let prev_len = self.rb.advance_tail(tail, nitems);
+        if prev_len == 0 && nitems > 0 {
+            if let Some(on_nonempty) = &self.on_nonempty {
+                on_nonempty();
+            }
+        }
+    }
+
     /// Pushes an item to the `RingBuffer`.
     ///
     /// Returns `Some` on success. Returns `None` if
@@ -265,7 +355,7 @@ impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
         let mut writer = rb.segment.writer().skip(segment_offset);
         writer.write_val(&item).unwrap();
 
-        rb.advance_tail(tail, 1);
+        self.commit_push(tail, 1);
         Some(())
     }
 
@@ -282,23 +372,74 @@ impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
         }
 
         let tail = rb.tail();
-        debug_assert!(tail < rb.capacity);
-        let segment_offset = tail * Self::T_SIZE;
+        self.write_slice_at(tail, items);
+
+        self.commit_push(tail, nitems);
+        Some(())
+    }
+
+    /// Pushes as many leading items of the slice as the `RingBuffer` can hold.
+    ///
+    /// Returns the number of items pushed, which is zero if the ring buffer is full.
+    pub fn push_slice_partial(&mut self, items: &[T]) -> usize {
+        let nitems = items.len().min(self.rb.free_len());
+        if nitems == 0 {
+            return 0;
+        }
+
+        let tail = self.rb.tail();
+        self.write_slice_at(tail, &items[..nitems]);
 
-        if tail + nitems > rb.capacity {
+        self.commit_push(tail, nitems);
+        nitems
+    }
+
+    /// Writes the items to the free space starting from `pos`, without committing them.
+    fn write_slice_at(&self, pos: usize, items: &[T]) {
+        let rb = &self.rb;
+        debug_assert!(pos < rb.capacity);
+        let segment_offset = pos * Self::T_SIZE;
+
+        if pos + items.len() > rb.capacity {
             // Write into two separate parts
             rb.segment
-                .write_slice(segment_offset, &items[..rb.capacity - tail])
+                .write_slice(segment_offset, &items[..rb.capacity - pos])
                 .unwrap();
             rb.segment
-                .write_slice(0, &items[rb.capacity - tail..])
+                .write_slice(0, &items[rb.capacity - pos..])
                 .unwrap();
         } else {
             rb.segment.write_slice(segment_offset, items).unwrap();
         }
+    }
 
-        rb.advance_tail(tail, nitems);
-        Some(())
+    /// Pushes an item to the `RingBuffer`. The oldest item
+    /// will be overwritten if the buffer is full.
+    ///
+    /// Returns the overwritten item if any.
+    ///
+    /// Overwriting discards the oldest item on behalf of the consumer,
+    /// so this method must not race with a concurrent consumer. Otherwise,
+    /// the consumer may observe items that are being overwritten, or an item
+    /// may be both popped and returned as overwritten.
+    pub fn push_overwrite(&mut self, item: T) -> Option<T> {
+        let rb = &self.rb;
+        let overwritten = if rb.is_full() {
+            let head = rb.head();
+            debug_assert!(head < rb.capacity);
+
+            let segment_offset = head * Self::T_SIZE;
+            let mut reader = rb.segment.reader().skip(segment_offset);
+            let overwritten = reader.read_val::<T>().unwrap();
+
+            rb.advance_head(head, 1);
+            Some(overwritten)
+        } else {
+            None
+        };
+
+        self.push(item).unwrap();
+        overwritten
     }
 }
 
@@ -328,9 +469,102 @@ impl<R: Deref<Target = RingBuffer<u8>>> Producer<u8, R> {
             reader.read(&mut writer)?
         };
 
-        rb.advance_tail(tail, write_len);
+        self.commit_push(tail, write_len);
         Ok(write_len)
     }
+
+    /// Writes a frame, i.e., a `u32` length prefix in native endian followed by `bytes`.
+    ///
+    /// The frame is written atomically: the consumer either sees the whole frame
+    /// or nothing of it.
+    ///
+    /// Returns `Some` on success. Returns `None` if the free space cannot hold the whole frame.
+    pub fn write_frame(&mut self, bytes: &[u8]) -> Option<()> {
+        let len = u32::try_from(bytes.len()).ok()?;
+        let rb = &self.rb;
+        let frame_len = FRAME_PREFIX_LEN.checked_add(bytes.len())?;
+        if rb.free_len() < frame_len {
+            return None;
+        }
+
+        let tail = rb.tail();
+        self.write_slice_at(tail, &len.to_ne_bytes());
+        self.write_slice_at((tail + FRAME_PREFIX_LEN) & (rb.capacity - 1), bytes);
+
+        self.commit_push(tail, frame_len);
+        Some(())
+    }
+
+    /// Reserves a contiguous span of at most `max_len` free bytes.
+    ///
+    /// This allows writing data whose final length is only known after it is
+    /// written, e.g., a serialized packet. The span never wraps around the end
+    /// of the `RingBuffer`, so the reserved length may be less than `max_len`
+    /// even if there is enough free space.
+    ///
+    /// Returns `None` if no bytes can be reserved.
+    pub fn reserve(&mut self, max_len: usize) -> Option<Reservation<'_, R>> {
+        let rb = &self.rb;
+        let pos = rb.tail();
+        let len = max_len.min(rb.free_len()).min(rb.capacity - pos);
+        if len == 0 {
+            return None;
+        }
+
+        Some(Reservation {
+            producer: self,
+            pos,
+            len,
+        })
+    }
+}
+
+impl<R: Deref<Target = RingBuffer<u8>>> Reservation<'_, R> {
+    /// Gets the number of reserved bytes.
+    pub fn reserved_len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns a writer to the reserved bytes.
+    pub fn writer(&self) -> VmWriter<'_, Infallible> {
+        self.producer
+            .rb
+            .segment
+            .writer()
+            .skip(self.pos)
+            .limit(self.len)
+    }
+
+    /// Commits the first `used` reserved bytes, making them visible to the consumer.
+    ///
+    /// The rest of the reserved bytes remain free.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `used` is greater than the number of reserved bytes.
+    pub fn commit(self, used: usize) {
+        assert!(used <= self.len, "cannot commit more than reserved");
+        self.producer.commit_push(self.pos, used);
+    }
+}
+
+impl<T, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
+    fn new(rb: R) -> Self {
+        Self {
+            rb,
+            on_nonempty: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets a callback that is invoked whenever a write by this producer
+    /// makes the `RingBuffer` transition from empty to non-empty.
+    ///
+    /// The callback is invoked after the items become visible to the consumer,
+    /// so it is suitable for waking up a consumer waiting for new items.
+    pub fn set_on_nonempty(&mut self, cb: impl Fn() + Send + Sync + 'static) {
+        self.on_nonempty = Some(Box::new(cb));
+    }
 }
 
 #[inherit_methods(from = "self.rb")]
@@ -340,11 +574,21 @@ impl<T, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
     pub fn is_full(&self) -> bool;
     pub fn len(&self) -> usize;
     pub fn free_len(&self) -> usize;
+    pub fn snapshot(&self) -> RingSnapshot;
 }
 
 impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Consumer<T, R> {
     const T_SIZE: usize = core::mem::size_of::<T>();
 
+    fn commit_pop(&self, head: usize, nitems: usize) {
+        let prev_len = self.rb.advance_head(head, nitems);
+        if prev_len == self.rb.capacity && nitems > 0 {
+            if let Some(on_nonfull) = &self.on_nonfull {
+                on_nonfull();
+            }
+        }
+    }
+
     /// Pops an item from the `RingBuffer`.
     ///
     /// Returns `Some` with the popped item on success.
@@ -362,7 +606,7 @@ impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Consumer<T, R> {
         let mut reader = rb.segment.reader().skip(segment_offset);
         let item = reader.read_val::<T>().unwrap();
 
-        rb.advance_head(head, 1);
+        self.commit_pop(head, 1);
         Some(item)
     }
 
@@ -378,23 +622,63 @@ impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Consumer<T, R> {
         }
 
         let head = rb.head();
-        debug_assert!(head < rb.capacity);
+        self.read_slice_at(head, items);
 
-        let segment_offset = head * Self::T_SIZE;
-        if head + nitems > rb.capacity {
+        self.commit_pop(head, nitems);
+        Some(())
+    }
+
+    /// Pops as many items as available to fill the leading part of the slice.
+    ///
+    /// Returns the number of items popped, which is zero if the ring buffer is empty.
+    pub fn pop_slice_partial(&mut self, items: &mut [T]) -> usize {
+        let nitems = items.len().min(self.rb.len());
+        if nitems == 0 {
+            return 0;
+        }
+
+        let head = self.rb.head();
+        self.read_slice_at(head, &mut items[..nitems]);
+
+        self.commit_pop(head, nitems);
+        nitems
+    }
+
+    /// Reads the items starting from `pos` to `items`, without consuming them.
+    fn read_slice_at(&self, pos: usize, items: &mut [T]) {
+        let rb = &self.rb;
+        debug_assert!(pos < rb.capacity);
+        let segment_offset = pos * Self::T_SIZE;
+
+        if pos + items.len() > rb.capacity {
             // Read from two separate parts
             rb.segment
-                .read_slice(segment_offset, &mut items[..rb.capacity - head])
+                .read_slice(segment_offset, &mut items[..rb.capacity - pos])
                 .unwrap();
             rb.segment
-                .read_slice(0, &mut items[rb.capacity - head..])
+                .read_slice(0, &mut items[rb.capacity - pos..])
                 .unwrap();
         } else {
             rb.segment.read_slice(segment_offset, items).unwrap();
         }
+    }
 
-        rb.advance_head(head, nitems);
-        Some(())
+    /// Returns an iterator that pops items until the `RingBuffer` is empty.
+    ///
+    /// The iterator reflects live availability: items pushed by the producer
+    /// while draining are also yielded.
+    pub fn drain(&mut self) -> Drain<'_, T, R> {
+        self.drain_at_most(usize::MAX)
+    }
+
+    /// Returns an iterator that pops at most `n` items.
+    ///
+    /// The iterator stops early if the `RingBuffer` becomes empty.
+    pub fn drain_at_most(&mut self, n: usize) -> Drain<'_, T, R> {
+        Drain {
+            consumer: self,
+            remaining: n,
+        }
     }
 }
 
@@ -424,9 +708,54 @@ impl<R: Deref<Target = RingBuffer<u8>>> Consumer<u8, R> {
             writer.write(&mut reader)?
         };
 
-        rb.advance_head(head, read_len);
+        self.commit_pop(head, read_len);
         Ok(read_len)
     }
+
+    /// Reads a frame written by [`Producer::write_frame`].
+    ///
+    /// Returns `Some` with the payload of the frame on success.
+    /// Returns `None` if no whole frame is available, in which case nothing is consumed.
+    pub fn read_frame(&mut self) -> Option<Vec<u8>> {
+        let rb = &self.rb;
+        let len = rb.len();
+        if len < FRAME_PREFIX_LEN {
+            return None;
+        }
+
+        let head = rb.head();
+        let mut prefix = [0u8; FRAME_PREFIX_LEN];
+        self.read_slice_at(head, &mut prefix);
+        let payload_len = u32::from_ne_bytes(prefix) as usize;
+        if len - FRAME_PREFIX_LEN < payload_len {
+            return None;
+        }
+
+        let mut payload = vec![0u8; payload_len];
+        self.read_slice_at((head + FRAME_PREFIX_LEN) & (rb.capacity - 1), &mut payload);
+
+        self.commit_pop(head, FRAME_PREFIX_LEN + payload_len);
+        Some(payload)
+    }
+}
+
+impl<T, R: Deref<Target = RingBuffer<T>>> Consumer<T, R> {
+    fn new(rb: R) -> Self {
+        Self {
+            rb,
+            on_nonfull: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets a callback that is invoked whenever a read by this consumer
+    /// makes the `RingBuffer` transition from full to non-full.
+    ///
+    /// The callback is invoked after the freed space becomes visible to the producer,
+    /// so it is suitable for waking up a producer waiting for free space.
+    pub fn set_on_nonfull(&mut self, cb: impl Fn() + Send + Sync + 'static) {
+        self.on_nonfull = Some(Box::new(cb));
+    }
 }
 
 #[inherit_methods(from = "self.rb")]
@@ -436,6 +765,20 @@ impl<T, R: Deref<Target = RingBuffer<T>>> Consumer<T, R> {
     pub fn is_full(&self) -> bool;
     pub fn len(&self) -> usize;
     pub fn free_len(&self) -> usize;
+    pub fn snapshot(&self) -> RingSnapshot;
+}
+
+impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Iterator for Drain<'_, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.consumer.pop()?;
+        self.remaining -= 1;
+        Some(item)
+    }
 }
 
 #[cfg(ktest)]
@@ -473,6 +816,158 @@ mod test {
         assert!(rb.is_empty());
     }
 
+    #[ktest]
+    fn test_rb_from_segment() {
+        let alloc_segment = || FrameAllocOptions::new().alloc_segment(1).unwrap();
+        let capacity = PAGE_SIZE / size_of::<u64>();
+
+        let mut rb = RingBuffer::<u64>::from_segment(alloc_segment(), capacity).unwrap();
+        for i in 0..capacity as u64 {
+            rb.push(i).unwrap();
+        }
+        assert!(rb.is_full());
+        for i in 0..capacity as u64 {
+            assert_eq!(rb.pop(), Some(i));
+        }
+        assert!(rb.is_empty());
+
+        assert!(RingBuffer::<u64>::from_segment(alloc_segment(), capacity * 2).is_err());
+        assert!(RingBuffer::<u64>::from_segment(alloc_segment(), capacity - 1).is_err());
+    }
+
+    #[ktest]
+    fn test_rb_iter() {
+        let mut rb = RingBuffer::<u32>::new(8);
+        assert_eq!(rb.iter().count(), 0);
+
+        rb.push_slice(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let mut popped = [0u32; 4];
+        rb.pop_slice(&mut popped).unwrap();
+        rb.push_slice(&[7, 8, 9]).unwrap();
+
+        // The items wrap around the end of the ring buffer.
+        assert_eq!(rb.iter().collect::<Vec<_>>(), [5, 6, 7, 8, 9]);
+        assert_eq!(rb.len(), 5);
+        assert_eq!(rb.pop(), Some(5));
+    }
+
+    #[cfg(debug_assertions)]
+    #[ktest]
+    #[should_panic(expected = "the head overtakes the tail")]
+    fn test_rb_head_overtakes_tail() {
+        let rb = RingBuffer::<u8>::new(4);
+        rb.advance_tail(rb.tail(), 2);
+        // Consuming more items than present desynchronizes the head and the tail.
+        rb.advance_head(rb.head(), 3);
+    }
+
+    #[cfg(debug_assertions)]
+    #[ktest]
+    #[should_panic(expected = "the tail overtakes the head")]
+    fn test_rb_tail_overtakes_head() {
+        let rb = RingBuffer::<u8>::new(4);
+        rb.advance_tail(rb.tail(), 3);
+        // Producing more items than the free space overwrites the unconsumed items.
+        rb.advance_tail(rb.tail(), 2);
+    }
+
+    #[ktest]
+    fn test_rb_partial_slices() {
+        let rb = RingBuffer::<u16>::new(8);
+        let (mut prod, mut cons) = rb.split();
+
+        assert_eq!(prod.push_slice_partial(&[1, 2, 3, 4, 5, 6]), 6);
+        assert_eq!(prod.push_slice_partial(&[7, 8, 9, 10]), 2);
+        assert_eq!(prod.push_slice_partial(&[11]), 0);
+        assert!(prod.is_full());
+
+        let mut popped = [0u16; 5];
+        assert_eq!(cons.pop_slice_partial(&mut popped), 5);
+        assert_eq!(popped, [1, 2, 3, 4, 5]);
+
+        // The pushed items wrap around the end of the ring buffer.
+        assert_eq!(prod.push_slice_partial(&[9, 10, 11, 12, 13, 14]), 5);
+
+        let mut popped = [0u16; 10];
+        assert_eq!(cons.pop_slice_partial(&mut popped), 8);
+        assert_eq!(popped, [6, 7, 8, 9, 10, 11, 12, 13, 0, 0]);
+        assert_eq!(cons.pop_slice_partial(&mut popped), 0);
+        assert!(cons.is_empty());
+    }
+
+    #[ktest]
+    fn test_rb_snapshot() {
+        let rb = RingBuffer::<u32>::new(8);
+        let (mut prod, mut cons) = rb.split();
+
+        prod.push_slice(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let snapshot = prod.snapshot();
+        assert_eq!(
+            snapshot,
+            RingSnapshot {
+                head: 0,
+                tail: 6,
+                len: 6,
+                capacity: 8,
+            }
+        );
+        assert_eq!(cons.snapshot(), snapshot);
+
+        let mut popped = [0u32; 5];
+        cons.pop_slice(&mut popped).unwrap();
+        prod.push_slice(&[7, 8, 9, 10]).unwrap();
+        let snapshot = cons.snapshot();
+        assert_eq!(
+            snapshot,
+            RingSnapshot {
+                head: 5,
+                tail: 2,
+                len: 5,
+                capacity: 8,
+            }
+        );
+        assert_eq!(snapshot.len, (snapshot.tail + 8 - snapshot.head) % 8);
+        assert_eq!(prod.snapshot(), snapshot);
+    }
+
+    #[ktest]
+    fn test_rb_reserve_commit() {
+        let rb = RingBuffer::<u8>::new(128);
+        let (mut prod, mut cons) = rb.split();
+
+        let reservation = prod.reserve(64).unwrap();
+        assert_eq!(reservation.reserved_len(), 64);
+        let data: Vec<u8> = (0..40).collect();
+        let mut writer = reservation.writer();
+        assert_eq!(writer.write(&mut VmReader::from(data.as_slice())), 40);
+        reservation.commit(40);
+        assert_eq!(cons.len(), 40);
+
+        let mut popped = [0u8; 40];
+        cons.pop_slice(&mut popped).unwrap();
+        assert_eq!(popped.as_slice(), data.as_slice());
+
+        // A reservation that is not committed leaves the ring buffer untouched.
+        {
+            let reservation = prod.reserve(16).unwrap();
+            let mut writer = reservation.writer();
+            assert_eq!(
+                writer.write(&mut VmReader::from([0xffu8; 16].as_slice())),
+                16
+            );
+        }
+        assert!(cons.is_empty());
+
+        // The reserved span does not wrap around.
+        let reservation = prod.reserve(128).unwrap();
+        assert_eq!(reservation.reserved_len(), 128 - 40);
+        reservation.commit(0);
+        assert!(cons.is_empty());
+
+        prod.push_slice(&[0u8; 128]).unwrap();
+        assert!(prod.reserve(1).is_none());
+    }
+
     #[ktest]
     fn test_rb_write_read_one() {
         let rb = RingBuffer::<u8>::new(1);
@@ -556,6 +1051,81 @@ mod test {
         assert!(prod.is_empty());
     }
 
+    #[ktest]
+    fn test_rb_transition_callbacks() {
+        let rb = RingBuffer::<u8>::new(2);
+        let (mut prod, mut cons) = rb.split();
+
+        let nonempty_count = Arc::new(AtomicUsize::new(0));
+        let nonempty_count_cloned = nonempty_count.clone();
+        prod.set_on_nonempty(move || {
+            nonempty_count_cloned.fetch_add(1, Ordering::Relaxed);
+        });
+        let nonfull_count = Arc::new(AtomicUsize::new(0));
+        let nonfull_count_cloned = nonfull_count.clone();
+        cons.set_on_nonfull(move || {
+            nonfull_count_cloned.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // 0 -> 1 fires the callback, but 1 -> 2 does not.
+        prod.push(1).unwrap();
+        assert_eq!(nonempty_count.load(Ordering::Relaxed), 1);
+        prod.push(2).unwrap();
+        assert_eq!(nonempty_count.load(Ordering::Relaxed), 1);
+        assert!(prod.push(3).is_none());
+        assert_eq!(nonempty_count.load(Ordering::Relaxed), 1);
+
+        // Full -> 1 fires the callback, but 1 -> 0 does not.
+        assert_eq!(cons.pop().unwrap(), 1);
+        assert_eq!(nonfull_count.load(Ordering::Relaxed), 1);
+        assert_eq!(cons.pop().unwrap(), 2);
+        assert_eq!(nonfull_count.load(Ordering::Relaxed), 1);
+
+        // Empty writes do not count as a transition.
+        prod.push_slice(&[]).unwrap();
+        assert_eq!(nonempty_count.load(Ordering::Relaxed), 1);
+        prod.push_slice(&[4, 5]).unwrap();
+        assert_eq!(nonempty_count.load(Ordering::Relaxed), 2);
+    }
+
+    #[ktest]
+    fn test_rb_drain() {
+        let rb = RingBuffer::<u32>::new(8);
+        let (mut prod, mut cons) = rb.split();
+
+        prod.push_slice(&[1, 2, 3, 4, 5]).unwrap();
+        let drained: Vec<u32> = cons.drain_at_most(2).collect();
+        assert_eq!(drained, [1, 2]);
+        assert_eq!(cons.len(), 3);
+
+        prod.push_slice(&[6, 7]).unwrap();
+        let drained: Vec<u32> = cons.drain().collect();
+        assert_eq!(drained, [3, 4, 5, 6, 7]);
+        assert!(cons.is_empty());
+        assert_eq!(cons.drain().next(), None);
+    }
+
+    #[ktest]
+    fn test_rb_push_overwrite() {
+        const CAPACITY: usize = 4;
+
+        let rb = RingBuffer::<usize>::new(CAPACITY);
+        let (mut prod, mut cons) = rb.split();
+
+        for i in 0..2 * CAPACITY {
+            let overwritten = prod.push_overwrite(i);
+            if i < CAPACITY {
+                assert_eq!(overwritten, None);
+            } else {
+                assert_eq!(overwritten, Some(i - CAPACITY));
+            }
+        }
+        assert!(cons.is_full());
+
+        let kept: Vec<usize> = cons.drain().collect();
+        assert_eq!(kept, (CAPACITY..2 * CAPACITY).collect::<Vec<_>>());
+    }
+
     fn reader_from(buf: &[u8]) -> VmReader {
         VmReader::from(buf).to_fallible()
     }
@@ -563,4 +1133,40 @@ mod test {
     fn writer_from(buf: &mut [u8]) -> VmWriter {
         VmWriter::from(buf).to_fallible()
     }
+
+    #[ktest]
+    fn test_rb_frames() {
+        let rb = RingBuffer::<u8>::new(64);
+        let (mut producer, mut consumer) = rb.split();
+        assert_eq!(consumer.read_frame(), None);
+
+        // Interleave frames of different sizes, wrapping around the ring buffer.
+        for round in 0..10u8 {
+            let short = [round; 3];
+            let long = [round.wrapping_add(100); 21];
+            producer.write_frame(&short).unwrap();
+            producer.write_frame(&[]).unwrap();
+            producer.write_frame(&long).unwrap();
+
+            assert_eq!(consumer.read_frame().unwrap(), short);
+            assert!(consumer.read_frame().unwrap().is_empty());
+            assert_eq!(consumer.read_frame().unwrap(), long);
+            assert!(consumer.is_empty());
+        }
+
+        // A frame is written only if it fits as a whole.
+        producer.write_frame(&[1; 56]).unwrap();
+        assert_eq!(producer.write_frame(&[2; 1]), None);
+        assert_eq!(producer.len(), 60);
+        assert_eq!(consumer.read_frame().unwrap(), [1; 56]);
+
+        // A partial frame is not consumed.
+        producer.push_slice(&8u32.to_ne_bytes()).unwrap();
+        producer.push_slice(&[3; 5]).unwrap();
+        assert_eq!(consumer.read_frame(), None);
+        assert_eq!(consumer.len(), 9);
+        producer.push_slice(&[3; 3]).unwrap();
+        assert_eq!(consumer.read_frame().unwrap(), [3; 8]);
+        assert!(consumer.is_empty());
+    }
 }
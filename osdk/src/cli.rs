@@ -17,7 +17,7 @@ use crate::{
     },
 };
 
-use linux_bzimage_builder::PayloadEncoding;
+use linux_bzimage_builder::{PayloadEncoding, SetupProfile};
 
 pub fn main() {
     let load_config = |common_args: &CommonArgs| {
@@ -321,10 +321,72 @@ pub struct TestArgs {
         help = "Only run tests containing this string in their names"
     )]
     pub test_name: Option<String>,
+    #[arg(long, help = "Run ignored tests as well")]
+    pub include_ignored: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TestOutputFormat::Pretty,
+        help = "The output format of the test results"
+    )]
+    pub format: TestOutputFormat,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TestOrder::Default,
+        help = "The order in which the tests in each crate are run"
+    )]
+    pub order: TestOrder,
+    #[arg(
+        long,
+        value_name = "SEED",
+        help = "The seed to shuffle the tests with, which is random if not given"
+    )]
+    pub shuffle_seed: Option<u64>,
     #[command(flatten)]
     pub common_args: CommonArgs,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TestOutputFormat {
+    /// Colored human-readable output
+    Pretty,
+    /// The Test Anything Protocol
+    Tap,
+    /// A JSON summary of the test results
+    Json,
+}
+
+impl TestOutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestOutputFormat::Pretty => "pretty",
+            TestOutputFormat::Tap => "tap",
+            TestOutputFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TestOrder {
+    /// The order of the tests in the source tree
+    Default,
+    /// The reverse of the default order
+    Reverse,
+    /// A random order that can be replayed with `--shuffle-seed`
+    Shuffle,
+}
+
+impl TestOrder {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestOrder::Default => "default",
+            TestOrder::Reverse => "reverse",
+            TestOrder::Shuffle => "shuffle",
+        }
+    }
+}
+
 #[derive(Debug, Args, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CargoArgs {
     #[arg(
@@ -476,4 +538,11 @@ pub struct CommonArgs {
         global = true
     )]
     pub encoding: Option<PayloadEncoding>,
+    #[arg(
+        long = "setup-profile",
+        help = "The profile for building the Linux x86 setup binary ('release' or 'debug')",
+        value_name = "PROFILE",
+        global = true
+    )]
+    pub setup_profile: Option<SetupProfile>,
 }
@@ -8,7 +8,7 @@ use std::{
 };
 
 use linux_bzimage_builder::{
-    legacy32_rust_target_json, make_bzimage, BzImageType, PayloadEncoding,
+    legacy32_rust_target_json, make_bzimage, BzImageType, PayloadEncoding, SetupProfile,
 };
 
 use crate::{
@@ -18,6 +18,7 @@ use crate::{
         file::BundleFile,
     },
     util::{get_current_crate_info, hard_link_or_copy},
+    warn_msg,
 };
 
 pub fn make_install_bzimage(
@@ -26,6 +27,7 @@ pub fn make_install_bzimage(
     aster_elf: &AsterBin,
     linux_x86_legacy_boot: bool,
     encoding: PayloadEncoding,
+    setup_profile: SetupProfile,
 ) -> AsterBin {
     let target_name = get_current_crate_info().name;
     let image_type = if linux_x86_legacy_boot {
@@ -42,13 +44,14 @@ pub fn make_install_bzimage(
                 let gen_target_json_path = target_dir.as_ref().join("x86_64-i386_pm-none.json");
                 std::fs::write(&gen_target_json_path, target_json).unwrap();
                 let arch = SetupInstallArch::Other(gen_target_json_path.canonicalize().unwrap());
-                install_setup_with_arch(setup_install_dir, setup_target_dir, &arch);
+                install_setup_with_arch(setup_install_dir, setup_target_dir, &arch, setup_profile);
             }
             BzImageType::Efi64 => {
                 install_setup_with_arch(
                     setup_install_dir,
                     setup_target_dir,
                     &SetupInstallArch::X86_64,
+                    setup_profile,
                 );
             }
         };
@@ -160,6 +163,7 @@ fn install_setup_with_arch(
     install_dir: impl AsRef<Path>,
     target_dir: impl AsRef<Path>,
     arch: &SetupInstallArch,
+    profile: SetupProfile,
 ) {
     if !target_dir.as_ref().exists() {
         std::fs::create_dir_all(&target_dir).unwrap();
@@ -200,6 +204,10 @@ fn install_setup_with_arch(
     cmd.env("RUSTFLAGS", rustflags.join(" "));
     cmd.arg("install").arg("linux-bzimage-setup");
     cmd.arg("--force");
+    if profile == SetupProfile::Debug {
+        warn_msg!("Building the Linux x86 setup binary in debug mode, which is less stable");
+        cmd.arg("--debug");
+    }
     cmd.arg("--root").arg(install_dir.as_ref());
     if matches!(option_env!("OSDK_LOCAL_DEV"), Some("1")) {
         let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
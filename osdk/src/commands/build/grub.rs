@@ -58,6 +58,7 @@ pub fn create_bootdev_image(
                 aster_bin,
                 action.build.linux_x86_legacy_boot,
                 config.build.encoding.clone(),
+                config.build.setup_profile,
             );
         }
         _ => {
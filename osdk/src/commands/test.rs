@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use std::fs;
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use super::{build::do_cached_build, util::DEFAULT_TARGET_RELPATH};
 use crate::{
@@ -56,6 +59,13 @@ pub fn test_current_crate(config: &Config, args: &TestArgs) {
         None => r#"None"#.to_string(),
     };
 
+    // The seed is printed by the test runner, so a shuffled order can be
+    // replayed with `--shuffle-seed`.
+    let ktest_shuffle_seed = args.shuffle_seed.unwrap_or_else(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        now.as_nanos() as u64
+    });
+
     let mut ktest_crate_whitelist = vec![current_crate.name];
     if let Some(name) = &args.test_name {
         ktest_crate_whitelist.push(name.clone());
@@ -72,6 +82,14 @@ pub fn test_current_crate(config: &Config, args: &TestArgs) {
 pub static KTEST_TEST_WHITELIST: Option<&[&str]> = {};
 #[no_mangle]
 pub static KTEST_CRATE_WHITELIST: Option<&[&str]> = Some(&{:#?});
+#[no_mangle]
+pub static KTEST_RUN_IGNORED: bool = {};
+#[no_mangle]
+pub static KTEST_OUTPUT_FORMAT: &str = "{}";
+#[no_mangle]
+pub static KTEST_ORDER: &str = "{}";
+#[no_mangle]
+pub static KTEST_SHUFFLE_SEED: u64 = {};
 
 "#,
         if runner_self_test {
@@ -81,6 +99,10 @@ pub static KTEST_CRATE_WHITELIST: Option<&[&str]> = Some(&{:#?});
         },
         ktest_test_whitelist,
         ktest_crate_whitelist,
+        args.include_ignored,
+        args.format.name(),
+        args.order.name(),
+        ktest_shuffle_seed,
     );
     let mut main_rs_content = fs::read_to_string(&main_rs_path).unwrap();
     main_rs_content.push_str(&ktest_main_rs);
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use linux_bzimage_builder::PayloadEncoding;
+use linux_bzimage_builder::{PayloadEncoding, SetupProfile};
 
 use super::{inherit_optional, Boot, BootScheme, Grub, GrubScheme, Qemu, QemuScheme};
 
@@ -26,6 +26,7 @@ pub struct BuildScheme {
     #[serde(default)]
     pub strip_elf: bool,
     pub encoding: Option<PayloadEncoding>,
+    pub setup_profile: Option<SetupProfile>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -42,6 +43,9 @@ pub struct Build {
     #[serde(default)]
     pub strip_elf: bool,
     pub encoding: PayloadEncoding,
+    // The profile for building the Linux x86 setup binary.
+    #[serde(default)]
+    pub setup_profile: SetupProfile,
 }
 
 impl Default for Build {
@@ -54,6 +58,7 @@ impl Default for Build {
             linux_x86_legacy_boot: false,
             strip_elf: false,
             encoding: PayloadEncoding::default(),
+            setup_profile: SetupProfile::default(),
         }
     }
 }
@@ -79,6 +84,9 @@ impl Build {
         if let Some(encoding) = common_args.encoding.clone() {
             self.encoding.clone_from(&encoding);
         }
+        if let Some(setup_profile) = common_args.setup_profile {
+            self.setup_profile = setup_profile;
+        }
     }
 }
 
@@ -102,6 +110,9 @@ impl BuildScheme {
         if self.encoding.is_none() {
             self.encoding.clone_from(&parent.encoding);
         }
+        if self.setup_profile.is_none() {
+            self.setup_profile = parent.setup_profile;
+        }
     }
 
     pub fn finalize(self) -> Build {
@@ -113,6 +124,7 @@ impl BuildScheme {
             linux_x86_legacy_boot: self.linux_x86_legacy_boot,
             strip_elf: self.strip_elf,
             encoding: self.encoding.unwrap_or_default(),
+            setup_profile: self.setup_profile.unwrap_or_default(),
         }
     }
 }
@@ -9,20 +9,27 @@
 
 extern crate alloc;
 
+mod order;
 mod path;
+mod report;
 mod tree;
+mod watchdog;
 
 use alloc::{boxed::Box, collections::BTreeSet, string::String, vec::Vec};
-use core::{any::Any, format_args};
+use core::{any::Any, format_args, time::Duration};
 
 use ostd::{
     early_print, early_println,
     ktest::{
-        get_ktest_crate_whitelist, get_ktest_test_whitelist, KtestError, KtestItem, KtestIter,
+        get_ktest_crate_whitelist, get_ktest_order, get_ktest_output_format, get_ktest_run_ignored,
+        get_ktest_test_whitelist, KtestError, KtestHook, KtestHookIter, KtestHookKind, KtestItem,
+        KtestItemInfo, KtestIter, KtestOrder,
     },
+    sync::SpinLock,
+    timer::Jiffies,
 };
-use owo_colors::OwoColorize;
 use path::{KtestPath, SuffixTrie};
+use report::{Reporter, TestOutcome, TestStats};
 use tree::{KtestCrate, KtestTree};
 
 pub enum KtestResult {
@@ -30,6 +37,19 @@ pub enum KtestResult {
     Failed,
 }
 
+/// The reporter of the test run.
+///
+/// It is shared with the watchdog, which reports the test that times out
+/// before aborting the test run.
+static REPORTER: SpinLock<Option<Reporter>> = SpinLock::new(None);
+
+/// Runs `f` with the reporter of the test run.
+///
+/// The local IRQs are disabled so that the watchdog cannot interrupt a report.
+fn with_reporter<T>(f: impl FnOnce(&mut Reporter) -> T) -> T {
+    f(REPORTER.disable_irq().lock().as_mut().unwrap())
+}
+
 /// The entry point of the test runner.
 #[ostd::ktest::main]
 fn main() {
@@ -43,6 +63,8 @@ fn main() {
         match run_ktests(
             get_ktest_test_whitelist().map(|s| s.iter().map(|s| s.to_string())),
             get_ktest_crate_whitelist(),
+            get_ktest_run_ignored(),
+            get_ktest_order(),
         ) {
             KtestResult::Ok => exit_qemu(QemuExitCode::Success),
             KtestResult::Failed => exit_qemu(QemuExitCode::Failed),
@@ -82,6 +104,10 @@ fn panic_handler(info: &core::panic::PanicInfo) -> ! {
 /// If it is `Some`, only the tests whose test path being the suffix of any paths in the whitelist
 /// will be run.
 ///
+/// Tests marked with `#[ignore]` are skipped unless `run_ignored` is `true`.
+///
+/// The tests inside each crate are run in the given `order`.
+///
 /// Returns the test result interpreted as `ok` or `FAILED`.
 ///
 /// If a test inside a crate fails, the test runner will continue to run the rest of the tests
@@ -89,6 +115,8 @@ fn panic_handler(info: &core::panic::PanicInfo) -> ! {
 fn run_ktests<PathsIter>(
     test_whitelist: Option<PathsIter>,
     crate_whitelist: Option<&[&str]>,
+    run_ignored: bool,
+    order: KtestOrder,
 ) -> KtestResult
 where
     PathsIter: Iterator<Item = String>,
@@ -97,112 +125,173 @@ where
         test_whitelist.map(|paths| SuffixTrie::from_paths(paths.map(|p| KtestPath::from(&p))));
 
     let tree = KtestTree::from_iter(KtestIter::new());
-    early_print!(
-        "\n[ktest runner] running {} tests in {} crates\n",
-        tree.nr_tot_tests(),
-        tree.nr_tot_crates()
-    );
+    let hooks: Vec<KtestHook> = KtestHookIter::new().collect();
+    *REPORTER.disable_irq().lock() = Some(Reporter::new(get_ktest_output_format(), order));
+    with_reporter(|reporter| {
+        reporter.message(format_args!(
+            "running {} tests in {} crates",
+            tree.nr_tot_tests(),
+            tree.nr_tot_crates()
+        ));
+        match order {
+            KtestOrder::Default => {}
+            KtestOrder::Reverse => reporter.message(format_args!("running tests in reverse order")),
+            KtestOrder::Shuffle(seed) => {
+                reporter.message(format_args!("shuffling tests with seed {}", seed))
+            }
+        }
+    });
     let crate_set =
         crate_whitelist.map(|crates| crates.iter().copied().collect::<BTreeSet<&str>>());
     for crate_ in tree.iter() {
         if let Some(crate_set) = &crate_set {
             if !crate_set.contains(crate_.name()) {
-                early_print!("\n[ktest runner] skipping crate \"{}\".\n", crate_.name());
+                with_reporter(|reporter| {
+                    reporter.message(format_args!("skipping crate \"{}\".", crate_.name()))
+                });
                 continue;
             }
         }
-        match run_crate_ktests(crate_, &whitelist_trie) {
+        match run_crate_ktests(crate_, &hooks, &whitelist_trie, run_ignored, order) {
             KtestResult::Ok => {}
-            KtestResult::Failed => return KtestResult::Failed,
+            KtestResult::Failed => {
+                with_reporter(|reporter| reporter.finish());
+                return KtestResult::Failed;
+            }
         }
     }
-    early_print!("\n[ktest runner] All crates tested.\n");
+    with_reporter(|reporter| {
+        reporter.message(format_args!("All crates tested."));
+        reporter.finish();
+    });
     KtestResult::Ok
 }
 
-fn run_crate_ktests(crate_: &KtestCrate, whitelist: &Option<SuffixTrie>) -> KtestResult {
+fn run_crate_ktests(
+    crate_: &KtestCrate,
+    hooks: &[KtestHook],
+    whitelist: &Option<SuffixTrie>,
+    run_ignored: bool,
+    order: KtestOrder,
+) -> KtestResult {
     let crate_name = crate_.name();
-    early_print!(
-        "\nrunning {} tests in crate \"{}\"\n\n",
-        crate_.nr_tot_tests(),
-        crate_name
-    );
+    with_reporter(|reporter| reporter.start_crate(crate_name, crate_.nr_tot_tests()));
 
-    let mut passed: usize = 0;
-    let mut filtered: usize = 0;
+    let mut stats = TestStats::default();
     let mut failed_tests: Vec<(KtestItem, KtestError)> = Vec::new();
-    for module in crate_.iter() {
-        for test in module.iter() {
-            if let Some(trie) = whitelist {
-                let mut test_path = KtestPath::from(test.info().module_path);
-                test_path.push_back(test.info().fn_name);
-                if !trie.contains(test_path.iter()) {
-                    filtered += 1;
-                    continue;
-                }
+    let mut tests: Vec<&KtestItem> = crate_.iter().flat_map(|module| module.iter()).collect();
+    order::reorder(&mut tests, order);
+    for test in tests {
+        if let Some(trie) = whitelist {
+            let mut test_path = KtestPath::from(test.info().module_path);
+            test_path.push_back(test.info().fn_name);
+            if !trie.contains(test_path.iter()) {
+                stats.filtered += 1;
+                continue;
             }
-            early_print!(
-                "test {}::{} ...",
-                test.info().module_path,
-                test.info().fn_name
-            );
-            debug_assert_eq!(test.info().package, crate_name);
-            match test.run(
-                &(ostd::panic::catch_unwind::<(), fn()>
-                    as fn(fn()) -> Result<(), Box<(dyn Any + Send + 'static)>>),
-            ) {
-                Ok(()) => {
-                    early_print!(" {}\n", "ok".green());
-                    passed += 1;
-                }
-                Err(e) => {
-                    early_print!(" {}\n", "FAILED".red());
-                    failed_tests.push((test.clone(), e.clone()));
+        }
+        with_reporter(|reporter| reporter.start_test(test.info()));
+        if test.is_ignored() && !run_ignored {
+            with_reporter(|reporter| {
+                reporter.end_test(test.info(), TestOutcome::Ignored, Duration::ZERO)
+            });
+            stats.ignored += 1;
+            continue;
+        }
+        debug_assert_eq!(test.info().package, crate_name);
+        let module_path = test.info().module_path;
+        let mut result = run_hooks(hooks, module_path, KtestHookKind::Setup);
+        let mut elapsed = Duration::ZERO;
+        if result.is_ok() {
+            if let Some(timeout_ms) = test.timeout_ms() {
+                watchdog::arm(test.info(), timeout_ms);
+            }
+            let start = Jiffies::elapsed().as_duration();
+            result = test.run(&CATCH_UNWIND);
+            elapsed = Jiffies::elapsed().as_duration() - start;
+            if let Some(timeout_ms) = test.timeout_ms() {
+                if watchdog::disarm() && result.is_ok() {
+                    result = Err(KtestError::Timeout(timeout_ms));
                 }
             }
         }
+        // The teardown hooks run even if the setup hooks or the test failed.
+        let teardown_result = run_hooks(hooks, module_path, KtestHookKind::Teardown);
+        if result.is_ok() {
+            result = teardown_result;
+        }
+        match result {
+            Ok(()) => {
+                with_reporter(|reporter| {
+                    reporter.end_test(test.info(), TestOutcome::Passed, elapsed)
+                });
+                stats.passed += 1;
+            }
+            Err(e) => {
+                with_reporter(|reporter| {
+                    reporter.end_test(test.info(), TestOutcome::Failed, elapsed)
+                });
+                failed_tests.push((test.clone(), e.clone()));
+            }
+        }
     }
-    let failed = failed_tests.len();
-    if failed == 0 {
-        early_print!("\ntest result: {}.", "ok".green());
-    } else {
-        early_print!("\ntest result: {}.", "FAILED".red());
+    stats.failed = failed_tests.len();
+    assert!(stats.nr_tests() == crate_.nr_tot_tests());
+    with_reporter(|reporter| reporter.end_crate(&stats, &failed_tests));
+    if stats.failed > 0 {
+        return KtestResult::Failed;
     }
+    KtestResult::Ok
+}
+
+type CatchUnwindImpl = fn(fn()) -> Result<(), Box<(dyn Any + Send + 'static)>>;
+
+const CATCH_UNWIND: CatchUnwindImpl = ostd::panic::catch_unwind::<(), fn()>;
+
+/// Runs the hooks of the given kind registered for the module.
+///
+/// All the hooks are run even if some of them fail. The first failure is
+/// returned.
+fn run_hooks(
+    hooks: &[KtestHook],
+    module_path: &str,
+    kind: KtestHookKind,
+) -> Result<(), KtestError> {
+    hooks
+        .iter()
+        .filter(|hook| hook.kind() == kind && hook.module_path() == module_path)
+        .map(|hook| hook.run(&CATCH_UNWIND))
+        .fold(Ok(()), |acc, result| acc.and(result))
+}
+
+/// Prints the details of a failed test.
+fn print_failure(info: &KtestItemInfo, error: &KtestError) {
     early_print!(
-        " {} passed; {} failed; {} filtered out.\n",
-        passed,
-        failed,
-        filtered
+        "---- {}:{}:{} - {} ----\n\n",
+        info.source,
+        info.line,
+        info.col,
+        info.fn_name
     );
-    assert!(passed + failed + filtered == crate_.nr_tot_tests());
-    if failed > 0 {
-        early_print!("\nfailures:\n\n");
-        for (t, e) in failed_tests {
+    match error {
+        KtestError::Panic(s) => {
+            early_print!("[caught panic] {}\n", s);
+        }
+        KtestError::ShouldPanicButNoPanic => {
+            early_print!("test did not panic as expected\n");
+        }
+        KtestError::ExpectedPanicNotMatch(expected, s) => {
+            early_print!("[caught panic] expected panic not match\n");
+            early_print!("expected: {}\n", expected);
+            early_print!("caught: {}\n", s);
+        }
+        KtestError::Timeout(timeout_ms) => {
+            early_print!("test did not finish within {} ms\n", timeout_ms);
+        }
+        KtestError::Unknown => {
             early_print!(
-                "---- {}:{}:{} - {} ----\n\n",
-                t.info().source,
-                t.info().line,
-                t.info().col,
-                t.info().fn_name
+                "[caught panic] unknown panic payload! (fatal panic handling error in ktest)\n"
             );
-            match e {
-                KtestError::Panic(s) => {
-                    early_print!("[caught panic] {}\n", s);
-                }
-                KtestError::ShouldPanicButNoPanic => {
-                    early_print!("test did not panic as expected\n");
-                }
-                KtestError::ExpectedPanicNotMatch(expected, s) => {
-                    early_print!("[caught panic] expected panic not match\n");
-                    early_print!("expected: {}\n", expected);
-                    early_print!("caught: {}\n", s);
-                }
-                KtestError::Unknown => {
-                    early_print!("[caught panic] unknown panic payload! (fatal panic handling error in ktest)\n");
-                }
-            }
         }
-        return KtestResult::Failed;
     }
-    KtestResult::Ok
 }
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The orders in which the tests in a crate are run.
+//!
+//! Running the tests in an order other than the one in the source tree reveals
+//! the tests that accidentally depend on the side effects of other tests.
+
+use ostd::ktest::KtestOrder;
+
+/// Reorders the tests of a crate, which are initially in the source tree order.
+pub fn reorder<T>(tests: &mut [T], order: KtestOrder) {
+    match order {
+        KtestOrder::Default => {}
+        KtestOrder::Reverse => tests.reverse(),
+        KtestOrder::Shuffle(seed) => shuffle(tests, seed),
+    }
+}
+
+/// Shuffles the items with the Fisher-Yates algorithm.
+///
+/// The random numbers are generated by SplitMix64 seeded with `seed`, so a
+/// seed always yields the same permutation and a failing order can be replayed.
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    for i in (1..items.len()).rev() {
+        let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::vec::Vec;
+
+    use ostd::prelude::ktest;
+
+    use super::*;
+
+    fn reordered(order: KtestOrder) -> Vec<usize> {
+        let mut items: Vec<usize> = (0..8).collect();
+        reorder(&mut items, order);
+        items
+    }
+
+    #[ktest]
+    fn test_reorder() {
+        assert_eq!(reordered(KtestOrder::Default), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(reordered(KtestOrder::Reverse), [7, 6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[ktest]
+    fn test_shuffle_is_deterministic() {
+        let shuffled = reordered(KtestOrder::Shuffle(42));
+        assert_eq!(shuffled, [3, 1, 6, 2, 4, 0, 7, 5]);
+        assert_eq!(reordered(KtestOrder::Shuffle(42)), shuffled);
+
+        let mut other = reordered(KtestOrder::Shuffle(7));
+        assert_ne!(other, shuffled);
+        other.sort();
+        assert_eq!(other, reordered(KtestOrder::Default));
+    }
+}
@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The reporter that prints the test results in the selected output format.
+//!
+//! There is no stdout in the kernel, so all the formats are printed with
+//! `early_print`. In the TAP format, the messages of the runner are printed as
+//! TAP diagnostics, i.e., lines starting with `#`. In the JSON format, nothing
+//! is printed until the summary at the end of the test run.
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt::Arguments, time::Duration};
+
+use ostd::{
+    early_print,
+    ktest::{KtestError, KtestItem, KtestItemInfo, KtestOrder, KtestOutputFormat},
+};
+use owo_colors::OwoColorize;
+
+use crate::print_failure;
+
+/// The outcome of a test that is not filtered out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// The numbers of tests grouped by their outcomes.
+#[derive(Clone, Debug, Default)]
+pub struct TestStats {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub filtered: usize,
+}
+
+impl TestStats {
+    pub fn nr_tests(&self) -> usize {
+        self.passed + self.failed + self.ignored + self.filtered
+    }
+}
+
+/// The number of the slowest tests listed at the end of the test run.
+const NR_SLOWEST_TESTS: usize = 5;
+
+pub struct Reporter {
+    format: KtestOutputFormat,
+    /// The order of the tests, which is recorded in the JSON summary.
+    order: KtestOrder,
+    /// The number of tests reported so far, which numbers the TAP lines.
+    nr_reported: usize,
+    total: TestStats,
+    failures: Vec<String>,
+    /// The slowest tests run so far, sorted from the slowest one.
+    slowest: Vec<(String, Duration)>,
+}
+
+impl Reporter {
+    pub fn new(format: KtestOutputFormat, order: KtestOrder) -> Self {
+        Self {
+            format,
+            order,
+            nr_reported: 0,
+            total: TestStats::default(),
+            failures: Vec::new(),
+            slowest: Vec::new(),
+        }
+    }
+
+    /// Prints a message about the progress of the test run.
+    pub fn message(&self, args: Arguments) {
+        match self.format {
+            KtestOutputFormat::Pretty => early_print!("\n[ktest runner] {}\n", args),
+            KtestOutputFormat::Tap => early_print!("# [ktest runner] {}\n", args),
+            KtestOutputFormat::Json => {}
+        }
+    }
+
+    pub fn start_crate(&self, crate_name: &str, nr_tests: usize) {
+        match self.format {
+            KtestOutputFormat::Pretty => {
+                early_print!(
+                    "\nrunning {} tests in crate \"{}\"\n\n",
+                    nr_tests,
+                    crate_name
+                );
+            }
+            KtestOutputFormat::Tap => {
+                early_print!("# running {} tests in crate \"{}\"\n", nr_tests, crate_name);
+            }
+            KtestOutputFormat::Json => {}
+        }
+    }
+
+    pub fn start_test(&self, info: &KtestItemInfo) {
+        if self.format == KtestOutputFormat::Pretty {
+            early_print!("test {}::{} ...", info.module_path, info.fn_name);
+        }
+    }
+
+    /// Reports the outcome of a test.
+    ///
+    /// The `elapsed` time is the time taken to run the test, which is
+    /// meaningless if the test is ignored.
+    pub fn end_test(&mut self, info: &KtestItemInfo, outcome: TestOutcome, elapsed: Duration) {
+        self.nr_reported += 1;
+        match outcome {
+            TestOutcome::Passed => self.total.passed += 1,
+            TestOutcome::Failed => {
+                self.total.failed += 1;
+                self.failures
+                    .push(format!("{}::{}", info.module_path, info.fn_name));
+            }
+            TestOutcome::Ignored => self.total.ignored += 1,
+        }
+        if outcome != TestOutcome::Ignored {
+            self.record_elapsed(info, elapsed);
+        }
+        let elapsed = format_duration(elapsed);
+        match self.format {
+            KtestOutputFormat::Pretty => match outcome {
+                TestOutcome::Passed => early_print!(" {} ({})\n", "ok".green(), elapsed),
+                TestOutcome::Failed => early_print!(" {} ({})\n", "FAILED".red(), elapsed),
+                TestOutcome::Ignored => early_print!(" {}\n", "ignored".yellow()),
+            },
+            KtestOutputFormat::Tap => {
+                early_print!("{}\n", tap_line(self.nr_reported, outcome, info));
+            }
+            KtestOutputFormat::Json => {}
+        }
+    }
+
+    pub fn end_crate(&mut self, stats: &TestStats, failed_tests: &[(KtestItem, KtestError)]) {
+        // The other outcomes are counted as the tests end.
+        self.total.filtered += stats.filtered;
+        match (self.format, stats.failed) {
+            (KtestOutputFormat::Pretty, 0) => early_print!("\ntest result: {}.", "ok".green()),
+            (KtestOutputFormat::Pretty, _) => early_print!("\ntest result: {}.", "FAILED".red()),
+            (KtestOutputFormat::Tap, 0) => early_print!("# test result: ok."),
+            (KtestOutputFormat::Tap, _) => early_print!("# test result: FAILED."),
+            (KtestOutputFormat::Json, _) => return,
+        }
+        early_print!(
+            " {} passed; {} failed; {} ignored; {} filtered out.\n",
+            stats.passed,
+            stats.failed,
+            stats.ignored,
+            stats.filtered
+        );
+        if !failed_tests.is_empty() {
+            early_print!("\nfailures:\n\n");
+            for (t, e) in failed_tests {
+                print_failure(t.info(), e);
+            }
+        }
+    }
+
+    /// Reports a failed test that aborts the test run, e.g., a test that times out.
+    ///
+    /// The test should have been reported with [`Self::end_test`].
+    pub fn abort(&self, info: &KtestItemInfo, error: &KtestError) {
+        if self.format == KtestOutputFormat::Json {
+            return;
+        }
+        early_print!("\nfailures:\n\n");
+        print_failure(info, error);
+        self.message(format_args!("test run aborted due to the failure."));
+    }
+
+    fn record_elapsed(&mut self, info: &KtestItemInfo, elapsed: Duration) {
+        let pos = self
+            .slowest
+            .iter()
+            .position(|(_, slow)| elapsed > *slow)
+            .unwrap_or(self.slowest.len());
+        if pos < NR_SLOWEST_TESTS {
+            let name = format!("{}::{}", info.module_path, info.fn_name);
+            self.slowest.insert(pos, (name, elapsed));
+            self.slowest.truncate(NR_SLOWEST_TESTS);
+        }
+    }
+
+    /// Finishes the test run, which may stop early after a crate fails.
+    pub fn finish(&self) {
+        let prefix = match self.format {
+            KtestOutputFormat::Pretty => "",
+            KtestOutputFormat::Tap => "# ",
+            KtestOutputFormat::Json => {
+                early_print!(
+                    "{}\n",
+                    json_summary(&self.total, &self.failures, self.order)
+                );
+                return;
+            }
+        };
+        if !self.slowest.is_empty() {
+            early_print!("\n{}slowest tests:\n", prefix);
+            for (name, elapsed) in self.slowest.iter() {
+                early_print!("{}    {} ({})\n", prefix, name, format_duration(*elapsed));
+            }
+        }
+        if self.format == KtestOutputFormat::Tap {
+            early_print!("1..{}\n", self.nr_reported);
+        }
+    }
+}
+
+/// Formats the time taken to run a test in milliseconds, e.g., `123 ms`.
+fn format_duration(duration: Duration) -> String {
+    format!("{} ms", duration.as_millis())
+}
+
+/// Formats the TAP line of a test, e.g., `not ok 2 - crate::module::test`.
+fn tap_line(number: usize, outcome: TestOutcome, info: &KtestItemInfo) -> String {
+    let status = match outcome {
+        TestOutcome::Failed => "not ok",
+        TestOutcome::Passed | TestOutcome::Ignored => "ok",
+    };
+    let directive = match outcome {
+        TestOutcome::Ignored => " # SKIP ignored",
+        TestOutcome::Passed | TestOutcome::Failed => "",
+    };
+    format!(
+        "{} {} - {}::{}{}",
+        status, number, info.module_path, info.fn_name, directive
+    )
+}
+
+/// Formats the JSON summary of a test run.
+///
+/// The summary includes the order of the tests, along with the seed if they are
+/// shuffled, so that a failing order can be replayed.
+///
+/// The names of the failed tests are Rust paths, so they need no escaping.
+fn json_summary(stats: &TestStats, failures: &[String], order: KtestOrder) -> String {
+    let failures = failures
+        .iter()
+        .map(|name| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(",");
+    let order = match order {
+        KtestOrder::Default => String::from("\"order\":\"default\""),
+        KtestOrder::Reverse => String::from("\"order\":\"reverse\""),
+        KtestOrder::Shuffle(seed) => format!("\"order\":\"shuffle\",\"seed\":{}", seed),
+    };
+    format!(
+        "{{\"passed\":{},\"failed\":{},\"ignored\":{},\"filtered\":{},\"failures\":[{}],{}}}",
+        stats.passed, stats.failed, stats.ignored, stats.filtered, failures, order
+    )
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::string::ToString;
+
+    use ostd::prelude::ktest;
+
+    use super::*;
+
+    fn info(module_path: &'static str, fn_name: &'static str) -> KtestItemInfo {
+        KtestItemInfo {
+            module_path,
+            fn_name,
+            package: "crate1",
+            source: "unrelated",
+            line: 0,
+            col: 0,
+        }
+    }
+
+    #[ktest]
+    fn test_tap_line() {
+        let info = info("crate1::mod1", "test1");
+        assert_eq!(
+            tap_line(1, TestOutcome::Passed, &info),
+            "ok 1 - crate1::mod1::test1"
+        );
+        assert_eq!(
+            tap_line(2, TestOutcome::Failed, &info),
+            "not ok 2 - crate1::mod1::test1"
+        );
+        assert_eq!(
+            tap_line(3, TestOutcome::Ignored, &info),
+            "ok 3 - crate1::mod1::test1 # SKIP ignored"
+        );
+    }
+
+    #[ktest]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0 ms");
+        assert_eq!(format_duration(Duration::from_micros(999)), "0 ms");
+        assert_eq!(format_duration(Duration::from_millis(42)), "42 ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500 ms");
+    }
+
+    #[ktest]
+    fn test_json_summary() {
+        let stats = TestStats {
+            passed: 3,
+            failed: 2,
+            ignored: 1,
+            filtered: 0,
+        };
+        let failures = [
+            "crate1::test1".to_string(),
+            "crate1::mod1::test2".to_string(),
+        ];
+        assert_eq!(
+            json_summary(&stats, &failures, KtestOrder::Default),
+            r#"{"passed":3,"failed":2,"ignored":1,"filtered":0,"failures":["crate1::test1","crate1::mod1::test2"],"order":"default"}"#
+        );
+        assert_eq!(
+            json_summary(&TestStats::default(), &[], KtestOrder::Reverse),
+            r#"{"passed":0,"failed":0,"ignored":0,"filtered":0,"failures":[],"order":"reverse"}"#
+        );
+        assert_eq!(
+            json_summary(&TestStats::default(), &[], KtestOrder::Shuffle(42)),
+            r#"{"passed":0,"failed":0,"ignored":0,"filtered":0,"failures":[],"order":"shuffle","seed":42}"#
+        );
+    }
+
+    #[ktest]
+    fn test_summary_of_aborted_run() {
+        let mut reporter = Reporter::new(KtestOutputFormat::Json, KtestOrder::Default);
+        reporter.end_test(
+            &info("crate1", "test1"),
+            TestOutcome::Passed,
+            Duration::ZERO,
+        );
+        // The watchdog reports a test that times out through the reporter before
+        // aborting the test run, so the summary counts it even if the crate never ends.
+        let timed_out = info("crate1::mod1", "test2");
+        reporter.end_test(&timed_out, TestOutcome::Failed, Duration::from_millis(100));
+        reporter.abort(&timed_out, &KtestError::Timeout(100));
+        assert_eq!(
+            json_summary(&reporter.total, &reporter.failures, reporter.order),
+            r#"{"passed":1,"failed":1,"ignored":0,"filtered":0,"failures":["crate1::mod1::test2"],"order":"default"}"#
+        );
+    }
+}
@@ -228,6 +228,8 @@ mod tests {
                 KtestItem::new(
                     dummy_fn,
                     (false, None),
+                    false,
+                    None,
                     ostd::ktest::KtestItemInfo {
                         module_path: m,
                         fn_name: f,
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The watchdog that catches the tests that do not finish within their timeouts.
+//!
+//! A hanging test would otherwise block the whole test run without telling
+//! which test is stuck. The watchdog is checked in the timer interrupt, so it
+//! cannot catch a test that hangs with local IRQs disabled.
+
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use ostd::{
+    arch::qemu::{exit_qemu, QemuExitCode},
+    ktest::{KtestError, KtestItemInfo},
+    sync::SpinLock,
+    timer::{self, Jiffies},
+};
+
+use crate::{report::TestOutcome, with_reporter};
+
+/// The test being watched.
+struct Watched {
+    info: KtestItemInfo,
+    timeout_ms: u64,
+    deadline: Duration,
+}
+
+static WATCHED: SpinLock<Option<Watched>> = SpinLock::new(None);
+static IS_CALLBACK_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Starts watching a test that should finish within `timeout_ms` milliseconds.
+///
+/// If the test is still running after the timeout, the test run is aborted
+/// with the test reported as timed out.
+pub fn arm(info: &KtestItemInfo, timeout_ms: u64) {
+    if !IS_CALLBACK_REGISTERED.swap(true, Ordering::Relaxed) {
+        timer::register_callback(check_deadline);
+    }
+
+    let deadline = Jiffies::elapsed().as_duration() + Duration::from_millis(timeout_ms);
+    *WATCHED.disable_irq().lock() = Some(Watched {
+        info: info.clone(),
+        timeout_ms,
+        deadline,
+    });
+}
+
+/// Stops watching the test.
+///
+/// Returns whether the test has exceeded its timeout, which may happen
+/// if the test finishes before the watchdog is checked.
+pub fn disarm() -> bool {
+    let watched = WATCHED.disable_irq().lock().take();
+    watched.is_some_and(|watched| Jiffies::elapsed().as_duration() >= watched.deadline)
+}
+
+fn check_deadline() {
+    let watched = WATCHED.disable_irq().lock();
+    let Some(watched) = watched.as_ref() else {
+        return;
+    };
+    if Jiffies::elapsed().as_duration() < watched.deadline {
+        return;
+    }
+
+    // The test cannot be stopped, so the only way to go on is to abort the test run.
+    let elapsed = Jiffies::elapsed().as_duration() - watched.deadline
+        + Duration::from_millis(watched.timeout_ms);
+    with_reporter(|reporter| {
+        reporter.end_test(&watched.info, TestOutcome::Failed, elapsed);
+        reporter.abort(&watched.info, &KtestError::Timeout(watched.timeout_ms));
+        reporter.finish();
+    });
+    exit_qemu(QemuExitCode::Failed);
+}
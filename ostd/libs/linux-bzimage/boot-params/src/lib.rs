@@ -10,9 +10,35 @@
 
 #![cfg_attr(not(test), no_std)]
 
+use int_to_c_enum::{TryFromInt, TryFromIntError};
+
 /// Magic stored in the boot protocol header.
 pub const LINUX_BOOT_HEADER_MAGIC: u32 = 0x53726448;
 
+/// The oldest boot protocol version that Asterinas accepts.
+///
+/// Version 2.12 is the first version that provides all the header fields
+/// Asterinas relies on, e.g., `init_size` (since 2.10) and `xloadflags`
+/// (since 2.12).
+pub const LINUX_BOOT_MIN_VERSION: u16 = 0x020c;
+
+/// The EFI loader signature of a 64-bit EFI firmware, i.e., `"EL64"`.
+pub const EFI64_LOADER_SIGNATURE: u32 = u32::from_le_bytes(*b"EL64");
+
+/// The EFI loader signature of a 32-bit EFI firmware, i.e., `"EL32"`.
+pub const EFI32_LOADER_SIGNATURE: u32 = u32::from_le_bytes(*b"EL32");
+
+/// Errors in validating the boot parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BootParamError {
+    /// The header magic is not [`LINUX_BOOT_HEADER_MAGIC`].
+    BadMagic(u32),
+    /// The boot protocol version is older than [`LINUX_BOOT_MIN_VERSION`].
+    UnsupportedVersion(u16),
+    /// The kernel is required to be relocatable but it is not.
+    NotRelocatable,
+}
+
 /// Linux 32/64-bit Boot Protocol parameter struct.
 ///
 /// Originally defined in the linux source tree:
@@ -57,11 +83,85 @@ pub struct BootParams {
     pub _pad9: [u8; 276],                           /* 0xeec */
 }
 
+impl BootParams {
+    /// Returns the 32-bit physical address of the kernel command line.
+    pub fn cmd_line_ptr(&self) -> u32 {
+        self.hdr.cmd_line_ptr()
+    }
+
+    /// Returns the 32-bit physical address of the initial ramdisk.
+    pub fn ramdisk_image(&self) -> u32 {
+        self.hdr.ramdisk_image()
+    }
+
+    /// Returns the size of the initial ramdisk in bytes.
+    pub fn ramdisk_size(&self) -> u32 {
+        self.hdr.ramdisk_size()
+    }
+
+    /// Returns the physical address of the ACPI RSDP, if the bootloader
+    /// provided one.
+    pub fn acpi_rsdp(&self) -> Option<u64> {
+        let rsdp = self.acpi_rsdp_addr;
+        (rsdp != 0).then_some(rsdp)
+    }
+
+    /// Returns the EFI information, if the kernel is booted by an EFI loader.
+    ///
+    /// The information is present only if `efi_loader_signature` is either
+    /// [`EFI64_LOADER_SIGNATURE`] or [`EFI32_LOADER_SIGNATURE`]. It is
+    /// returned by value since `EfiInfo` is not aligned within the packed
+    /// `BootParams`.
+    pub fn efi_info(&self) -> Option<EfiInfo> {
+        let efi_info = self.efi_info;
+        matches!(
+            efi_info.efi_loader_signature,
+            EFI64_LOADER_SIGNATURE | EFI32_LOADER_SIGNATURE
+        )
+        .then_some(efi_info)
+    }
+
+    /// Returns the valid entries of the E820 table.
+    ///
+    /// The number of entries is bounded by both `e820_entries` and the
+    /// capacity of the table, so a corrupted count will not cause an
+    /// out-of-bounds access.
+    pub fn e820_entries(&self) -> &[BootE820Entry] {
+        let nr_entries = (self.e820_entries as usize).min(E820_MAX_ENTRIES_ZEROPAGE);
+        &self.e820_table[..nr_entries]
+    }
+
+    /// Returns an iterator over copies of the valid E820 entries.
+    ///
+    /// Use [`E820Map::merged`] to coalesce adjacent entries of the same type.
+    pub fn e820_map(&self) -> E820Map<'_> {
+        E820Map {
+            entries: self.e820_entries().iter(),
+        }
+    }
+
+    /// Returns an iterator over the valid E820 entries as
+    /// `(start, end, type)` tuples.
+    ///
+    /// Entries of zero size are skipped. Entries whose type is unknown are
+    /// yielded as [`E820Type::Reserved`] since the kernel must not use them.
+    pub fn e820_regions(&self) -> impl Iterator<Item = (u64, u64, E820Type)> + '_ {
+        self.e820_entries().iter().filter_map(|entry| {
+            let (addr, size) = (entry.addr, entry.size);
+            if size == 0 {
+                return None;
+            }
+            let typ = entry.e820_type().unwrap_or(E820Type::Reserved);
+            Some((addr, addr.saturating_add(size), typ))
+        })
+    }
+}
+
 /// Linux Boot Protocol header.
 ///
 /// Originally defined in the linux source tree:
 /// `linux/arch/x86/include/uapi/asm/bootparam.h`
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 #[repr(C, packed)]
 pub struct SetupHeader {
     pub setup_sects: u8,
@@ -105,6 +205,241 @@ pub struct SetupHeader {
     pub kernel_info_offset: u32,
 }
 
+// The fields of a packed struct cannot be borrowed, so the getters below
+// copy them out by value, which compiles to unaligned reads.
+impl SetupHeader {
+    /// Returns the boot protocol magic, which should be [`LINUX_BOOT_HEADER_MAGIC`].
+    pub fn header(&self) -> u32 {
+        self.header
+    }
+
+    /// Returns the boot protocol version.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the 32-bit physical address of the kernel command line.
+    pub fn cmd_line_ptr(&self) -> u32 {
+        self.cmd_line_ptr
+    }
+
+    /// Returns the 32-bit physical address of the initial ramdisk.
+    pub fn ramdisk_image(&self) -> u32 {
+        self.ramdisk_image
+    }
+
+    /// Returns the size of the initial ramdisk in bytes.
+    pub fn ramdisk_size(&self) -> u32 {
+        self.ramdisk_size
+    }
+
+    /// Returns the offset of the payload from the start of the protected-mode code.
+    pub fn payload_offset(&self) -> u32 {
+        self.payload_offset
+    }
+
+    /// Returns the length of the payload in bytes.
+    pub fn payload_length(&self) -> u32 {
+        self.payload_length
+    }
+
+    /// Returns the amount of linear memory the kernel needs for initialization.
+    pub fn init_size(&self) -> u32 {
+        self.init_size
+    }
+
+    /// Validates the header magic and the boot protocol version.
+    pub fn validate(&self) -> Result<(), BootParamError> {
+        let header = self.header;
+        if header != LINUX_BOOT_HEADER_MAGIC {
+            return Err(BootParamError::BadMagic(header));
+        }
+        let version = self.version;
+        if version < LINUX_BOOT_MIN_VERSION {
+            return Err(BootParamError::UnsupportedVersion(version));
+        }
+        Ok(())
+    }
+
+    /// Validates the header like [`Self::validate`] and also checks that
+    /// the kernel is relocatable.
+    ///
+    /// Loaders that place the kernel at an address other than the preferred
+    /// one should use this method instead of [`Self::validate`].
+    pub fn validate_relocatable(&self) -> Result<(), BootParamError> {
+        self.validate()?;
+        if self.relocatable_kernel == 0 {
+            return Err(BootParamError::NotRelocatable);
+        }
+        Ok(())
+    }
+}
+
+/// A builder of [`SetupHeader`].
+///
+/// The built header carries the boot protocol magic, the boot flag and a
+/// protocol version that is recent enough for Asterinas. All the other fields
+/// are zero unless explicitly set.
+#[cfg(any(test, feature = "builder"))]
+#[derive(Clone, Debug)]
+pub struct SetupHeaderBuilder {
+    hdr: SetupHeader,
+}
+
+#[cfg(any(test, feature = "builder"))]
+impl SetupHeaderBuilder {
+    /// The boot protocol version of a header built by default.
+    pub const DEFAULT_VERSION: u16 = 0x020f;
+
+    /// Creates a builder of a minimal valid header.
+    pub fn new() -> Self {
+        let hdr = SetupHeader {
+            boot_flag: 0xAA55,
+            header: LINUX_BOOT_HEADER_MAGIC,
+            version: Self::DEFAULT_VERSION,
+            ..Default::default()
+        };
+        Self { hdr }
+    }
+
+    /// Sets the number of setup sectors.
+    pub fn setup_sects(&mut self, setup_sects: u8) -> &mut Self {
+        self.hdr.setup_sects = setup_sects;
+        self
+    }
+
+    /// Sets the boot protocol version.
+    pub fn version(&mut self, version: u16) -> &mut Self {
+        self.hdr.version = version;
+        self
+    }
+
+    /// Sets the 32-bit physical address of the kernel command line.
+    pub fn cmd_line_ptr(&mut self, cmd_line_ptr: u32) -> &mut Self {
+        self.hdr.cmd_line_ptr = cmd_line_ptr;
+        self
+    }
+
+    /// Sets the address and the size of the initial ramdisk.
+    pub fn ramdisk(&mut self, image: u32, size: u32) -> &mut Self {
+        self.hdr.ramdisk_image = image;
+        self.hdr.ramdisk_size = size;
+        self
+    }
+
+    /// Sets the offset and the length of the payload.
+    pub fn payload(&mut self, offset: u32, length: u32) -> &mut Self {
+        self.hdr.payload_offset = offset;
+        self.hdr.payload_length = length;
+        self
+    }
+
+    /// Sets the amount of linear memory the kernel needs for initialization.
+    pub fn init_size(&mut self, init_size: u32) -> &mut Self {
+        self.hdr.init_size = init_size;
+        self
+    }
+
+    /// Sets whether the kernel is relocatable.
+    pub fn relocatable_kernel(&mut self, relocatable: bool) -> &mut Self {
+        self.hdr.relocatable_kernel = relocatable as u8;
+        self
+    }
+
+    /// Builds the header.
+    pub fn build(&self) -> SetupHeader {
+        self.hdr
+    }
+}
+
+#[cfg(any(test, feature = "builder"))]
+impl Default for SetupHeaderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder of [`BootParams`], mainly for testing the code that consumes it.
+///
+/// The built parameters carry a header made by [`SetupHeaderBuilder::new`]
+/// and a zero `sentinel`. All the other fields are zero unless explicitly set.
+#[cfg(any(test, feature = "builder"))]
+#[derive(Clone, Debug)]
+pub struct BootParamsBuilder {
+    params: BootParams,
+}
+
+#[cfg(any(test, feature = "builder"))]
+impl BootParamsBuilder {
+    /// Creates a builder of minimal valid boot parameters.
+    pub fn new() -> Self {
+        // SAFETY: All fields of `BootParams` are integers, so zero is a valid value.
+        let mut params: BootParams = unsafe { core::mem::zeroed() };
+        params.hdr = SetupHeaderBuilder::new().build();
+        Self { params }
+    }
+
+    /// Sets the 32-bit physical address of the kernel command line.
+    pub fn with_cmdline_ptr(&mut self, cmd_line_ptr: u32) -> &mut Self {
+        self.params.hdr.cmd_line_ptr = cmd_line_ptr;
+        self
+    }
+
+    /// Appends an entry to the E820 table.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the E820 table is full.
+    pub fn add_e820_entry(&mut self, addr: u64, size: u64, typ: E820Type) -> &mut Self {
+        let idx = self.params.e820_entries as usize;
+        assert!(idx < E820_MAX_ENTRIES_ZEROPAGE, "the E820 table is full");
+        self.params.e820_table[idx] = BootE820Entry {
+            addr,
+            size,
+            typ: typ as u32,
+        };
+        self.params.e820_entries += 1;
+        self
+    }
+
+    /// Sets the physical address of the ACPI RSDP.
+    pub fn with_acpi_rsdp(&mut self, rsdp: u64) -> &mut Self {
+        self.params.acpi_rsdp_addr = rsdp;
+        self
+    }
+
+    /// Describes a linear framebuffer in an EFI graphic mode.
+    pub fn with_framebuffer(&mut self, fb: &Framebuffer) -> &mut Self {
+        let screen_info = &mut self.params.screen_info;
+        screen_info.orig_video_is_vga = VIDEO_TYPE_EFI;
+        screen_info.lfb_base = fb.base as u32;
+        screen_info.ext_lfb_base = (fb.base >> 32) as u32;
+        if screen_info.ext_lfb_base != 0 {
+            screen_info.capabilities |= VIDEO_CAPABILITY_64BIT_BASE;
+        }
+        screen_info.lfb_width = fb.width;
+        screen_info.lfb_height = fb.height;
+        screen_info.lfb_linelength = fb.stride;
+        screen_info.lfb_depth = fb.bpp;
+        (screen_info.red_size, screen_info.red_pos) = fb.red;
+        (screen_info.green_size, screen_info.green_pos) = fb.green;
+        (screen_info.blue_size, screen_info.blue_pos) = fb.blue;
+        self
+    }
+
+    /// Builds the boot parameters.
+    pub fn build(&self) -> BootParams {
+        self.params
+    }
+}
+
+#[cfg(any(test, feature = "builder"))]
+impl Default for BootParamsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
 pub struct ScreenInfo {
@@ -148,6 +483,64 @@ pub struct ScreenInfo {
     pub _reserved: [u8; 2],   /* 0x3e */
 }
 
+/// The video type of a VESA VGA in graphic mode, stored in `orig_video_is_vga`.
+pub const VIDEO_TYPE_VLFB: u8 = 0x23;
+
+/// The video type of an EFI graphic mode, stored in `orig_video_is_vga`.
+pub const VIDEO_TYPE_EFI: u8 = 0x70;
+
+/// The capability bit indicating that `ext_lfb_base` holds the high 32 bits
+/// of the framebuffer base.
+pub const VIDEO_CAPABILITY_64BIT_BASE: u32 = 1 << 1;
+
+impl ScreenInfo {
+    /// Returns the linear framebuffer described by the screen information.
+    ///
+    /// Returns `None` if the video is not in a graphic mode with a linear
+    /// framebuffer, e.g., in a VGA text mode.
+    pub fn framebuffer(&self) -> Option<Framebuffer> {
+        if !matches!(self.orig_video_is_vga, VIDEO_TYPE_VLFB | VIDEO_TYPE_EFI) {
+            return None;
+        }
+
+        let mut base = self.lfb_base as u64;
+        if self.capabilities & VIDEO_CAPABILITY_64BIT_BASE != 0 {
+            base |= (self.ext_lfb_base as u64) << 32;
+        }
+
+        Some(Framebuffer {
+            base,
+            width: self.lfb_width,
+            height: self.lfb_height,
+            stride: self.lfb_linelength,
+            bpp: self.lfb_depth,
+            red: (self.red_size, self.red_pos),
+            green: (self.green_size, self.green_pos),
+            blue: (self.blue_size, self.blue_pos),
+        })
+    }
+}
+
+/// A linear framebuffer set up by the bootloader.
+///
+/// The color channels are described as `(size, position)` pairs in bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    /// The physical address of the framebuffer.
+    pub base: u64,
+    /// The width in pixels.
+    pub width: u16,
+    /// The height in pixels.
+    pub height: u16,
+    /// The number of bytes per scan line.
+    pub stride: u16,
+    /// The number of bits per pixel.
+    pub bpp: u16,
+    pub red: (u8, u8),
+    pub green: (u8, u8),
+    pub blue: (u8, u8),
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
 pub struct ApmBiosInfo {
@@ -210,7 +603,7 @@ pub struct EfiInfo {
 ///
 /// Originally defined in the linux source tree:
 /// `linux/arch/x86/include/asm/e820/types.h`
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, TryFromInt)]
 #[repr(u32)]
 pub enum E820Type {
     Ram = 1,
@@ -224,12 +617,91 @@ pub enum E820Type {
     ReservedKern = 128,
 }
 
+/// An entry of the E820 memory map.
+///
+/// The type of the entry is kept as a raw integer since the table is filled
+/// by the bootloader or the firmware, which may report types unknown to
+/// [`E820Type`]. Use [`BootE820Entry::e820_type`] to decode it.
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
 pub struct BootE820Entry {
     pub addr: u64,
     pub size: u64,
-    pub typ: E820Type,
+    pub typ: u32,
+}
+
+impl BootE820Entry {
+    /// Decodes the type of the entry.
+    pub fn e820_type(&self) -> Result<E820Type, TryFromIntError> {
+        E820Type::try_from(self.typ)
+    }
+
+    /// Returns whether the entry describes RAM that is usable by the kernel.
+    pub fn is_usable_ram(&self) -> bool {
+        matches!(self.e820_type(), Ok(E820Type::Ram))
+    }
+}
+
+/// An iterator over the valid E820 entries of a [`BootParams`].
+///
+/// Created by [`BootParams::e820_map`].
+#[derive(Clone, Debug)]
+pub struct E820Map<'a> {
+    entries: core::slice::Iter<'a, BootE820Entry>,
+}
+
+impl E820Map<'_> {
+    /// Coalesces each run of adjacent entries of the same type into one.
+    ///
+    /// Two entries are adjacent if the second one starts exactly where the
+    /// first one ends. Overlapping entries are left untouched.
+    pub fn merged(self) -> MergedE820Map<Self> {
+        MergedE820Map {
+            inner: self,
+            pending: None,
+        }
+    }
+}
+
+impl Iterator for E820Map<'_> {
+    type Item = BootE820Entry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl ExactSizeIterator for E820Map<'_> {}
+
+/// An iterator that coalesces adjacent E820 entries of the same type.
+///
+/// Created by [`E820Map::merged`].
+#[derive(Clone, Debug)]
+pub struct MergedE820Map<I> {
+    inner: I,
+    pending: Option<BootE820Entry>,
+}
+
+impl<I: Iterator<Item = BootE820Entry>> Iterator for MergedE820Map<I> {
+    type Item = BootE820Entry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut merged = self.pending.take().or_else(|| self.inner.next())?;
+        for next in self.inner.by_ref() {
+            let (addr, size, typ) = (merged.addr, merged.size, merged.typ);
+            let (next_addr, next_size, next_typ) = (next.addr, next.size, next.typ);
+            if typ != next_typ || addr.checked_add(size) != Some(next_addr) {
+                self.pending = Some(next);
+                break;
+            }
+            merged.size = size + next_size;
+        }
+        Some(merged)
+    }
 }
 
 const E820_MAX_ENTRIES_ZEROPAGE: usize = 128;
@@ -334,4 +806,261 @@ mod tests {
         assert_eq!(offset_of!(BootParams, e820_table), 0x2d0);
         assert_eq!(offset_of!(BootParams, eddbuf), 0xd00);
     }
+
+    #[test]
+    fn test_setup_header_builder() {
+        let hdr = SetupHeaderBuilder::new()
+            .cmd_line_ptr(0x2_0000)
+            .ramdisk(0x100_0000, 0x1000)
+            .init_size(0x80_0000)
+            .build();
+        assert_eq!(hdr.header(), LINUX_BOOT_HEADER_MAGIC);
+        assert_eq!(hdr.version(), SetupHeaderBuilder::DEFAULT_VERSION);
+        assert_eq!(hdr.cmd_line_ptr(), 0x2_0000);
+        assert_eq!(hdr.ramdisk_image(), 0x100_0000);
+        assert_eq!(hdr.ramdisk_size(), 0x1000);
+        assert_eq!(hdr.init_size(), 0x80_0000);
+        assert_eq!(hdr.payload_offset(), 0);
+    }
+
+    #[test]
+    fn test_validate_setup_header() {
+        let hdr = SetupHeaderBuilder::new().build();
+        assert_eq!(hdr.validate(), Ok(()));
+        assert_eq!(
+            hdr.validate_relocatable(),
+            Err(BootParamError::NotRelocatable)
+        );
+
+        let hdr = SetupHeaderBuilder::new().relocatable_kernel(true).build();
+        assert_eq!(hdr.validate_relocatable(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_bad_magic() {
+        let mut hdr = SetupHeaderBuilder::new().build();
+        hdr.header = 0xdeadbeef;
+        assert_eq!(hdr.validate(), Err(BootParamError::BadMagic(0xdeadbeef)));
+    }
+
+    #[test]
+    fn test_validate_old_version() {
+        let hdr = SetupHeaderBuilder::new().version(0x0206).build();
+        assert_eq!(
+            hdr.validate(),
+            Err(BootParamError::UnsupportedVersion(0x0206))
+        );
+    }
+
+    #[test]
+    fn test_acpi_rsdp() {
+        // SAFETY: All fields of `BootParams` are integers, so zero is a valid value.
+        let mut params: BootParams = unsafe { core::mem::zeroed() };
+        assert_eq!(params.acpi_rsdp(), None);
+
+        params.acpi_rsdp_addr = 0xf_5a40;
+        assert_eq!(params.acpi_rsdp(), Some(0xf_5a40));
+    }
+
+    #[test]
+    fn test_efi_info() {
+        // SAFETY: All fields of `BootParams` are integers, so zero is a valid value.
+        let mut params: BootParams = unsafe { core::mem::zeroed() };
+        params.efi_info.efi_systab = 0x7fbe_e018;
+        assert!(params.efi_info().is_none());
+
+        params.efi_info.efi_loader_signature = 0x3436_4c45;
+        let efi_info = params.efi_info().unwrap();
+        assert_eq!(efi_info.efi_loader_signature, EFI64_LOADER_SIGNATURE);
+        assert_eq!(efi_info.efi_systab, 0x7fbe_e018);
+
+        params.efi_info.efi_loader_signature = EFI32_LOADER_SIGNATURE;
+        assert!(params.efi_info().is_some());
+    }
+
+    fn graphic_screen_info() -> ScreenInfo {
+        // SAFETY: All fields of `ScreenInfo` are integers, so zero is a valid value.
+        let mut screen_info: ScreenInfo = unsafe { core::mem::zeroed() };
+        screen_info.orig_video_is_vga = VIDEO_TYPE_EFI;
+        screen_info.lfb_base = 0x8000_0000;
+        screen_info.ext_lfb_base = 0x1;
+        screen_info.lfb_width = 1280;
+        screen_info.lfb_height = 800;
+        screen_info.lfb_depth = 32;
+        screen_info.lfb_linelength = 1280 * 4;
+        (screen_info.red_size, screen_info.red_pos) = (8, 16);
+        (screen_info.green_size, screen_info.green_pos) = (8, 8);
+        (screen_info.blue_size, screen_info.blue_pos) = (8, 0);
+        screen_info
+    }
+
+    #[test]
+    fn test_framebuffer() {
+        let mut screen_info = graphic_screen_info();
+        let fb = screen_info.framebuffer().unwrap();
+        assert_eq!(
+            fb,
+            Framebuffer {
+                base: 0x8000_0000,
+                width: 1280,
+                height: 800,
+                stride: 1280 * 4,
+                bpp: 32,
+                red: (8, 16),
+                green: (8, 8),
+                blue: (8, 0),
+            }
+        );
+
+        screen_info.capabilities = VIDEO_CAPABILITY_64BIT_BASE;
+        assert_eq!(screen_info.framebuffer().unwrap().base, 0x1_8000_0000);
+    }
+
+    #[test]
+    fn test_framebuffer_text_mode() {
+        let mut screen_info = graphic_screen_info();
+        // VGA color text mode.
+        screen_info.orig_video_is_vga = 0x22;
+        assert_eq!(screen_info.framebuffer(), None);
+    }
+
+    fn boot_params_with_e820(entries: &[(u64, u64, u32)]) -> BootParams {
+        // SAFETY: All fields of `BootParams` are integers, so zero is a valid value.
+        let mut params: BootParams = unsafe { core::mem::zeroed() };
+        for (i, &(addr, size, typ)) in entries.iter().enumerate() {
+            params.e820_table[i] = BootE820Entry { addr, size, typ };
+        }
+        params.e820_entries = entries.len() as u8;
+        params
+    }
+
+    fn e820_tuples(iter: impl Iterator<Item = BootE820Entry>) -> Vec<(u64, u64, u32)> {
+        iter.map(|entry| (entry.addr, entry.size, entry.typ))
+            .collect()
+    }
+
+    #[test]
+    fn test_e820_map() {
+        let entries = [(0x0, 0x9fc00, 1), (0x9fc00, 0x400, 2)];
+        let mut params = boot_params_with_e820(&entries);
+        params.e820_table[2] = BootE820Entry {
+            addr: 0x100000,
+            size: 0x1000,
+            typ: 1,
+        };
+
+        assert_eq!(params.e820_map().len(), 2);
+        assert_eq!(e820_tuples(params.e820_map()), entries);
+    }
+
+    #[test]
+    fn test_e820_map_merged() {
+        let params = boot_params_with_e820(&[
+            (0x0, 0x1000, 1),
+            (0x1000, 0x1000, 1),
+            (0x2000, 0x2000, 1),
+            (0x4000, 0x1000, 2),
+            (0x5000, 0x1000, 2),
+            // Overlaps the previous entry.
+            (0x5800, 0x1000, 2),
+            // Adjacent but of a different type.
+            (0x6800, 0x1000, 1),
+            // Not adjacent.
+            (0x8000, 0x1000, 1),
+        ]);
+
+        assert_eq!(
+            e820_tuples(params.e820_map().merged()),
+            [
+                (0x0, 0x4000, 1),
+                (0x4000, 0x2000, 2),
+                (0x5800, 0x1000, 2),
+                (0x6800, 0x1000, 1),
+                (0x8000, 0x1000, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_boot_params_builder() {
+        let fb = Framebuffer {
+            base: 0x1_8000_0000,
+            width: 1024,
+            height: 768,
+            stride: 1024 * 4,
+            bpp: 32,
+            red: (8, 16),
+            green: (8, 8),
+            blue: (8, 0),
+        };
+        let params = BootParamsBuilder::new()
+            .with_cmdline_ptr(0x2_0000)
+            .add_e820_entry(0x0, 0x9fc00, E820Type::Ram)
+            .add_e820_entry(0x9fc00, 0x400, E820Type::Reserved)
+            .add_e820_entry(0x100000, 0x7ee0000, E820Type::Ram)
+            .with_acpi_rsdp(0xf_5a40)
+            .with_framebuffer(&fb)
+            .build();
+
+        assert_eq!(params.hdr.validate(), Ok(()));
+        let sentinel = params.sentinel;
+        assert_eq!(sentinel, 0);
+        assert_eq!(params.cmd_line_ptr(), 0x2_0000);
+        assert_eq!(params.acpi_rsdp(), Some(0xf_5a40));
+        assert!(params.efi_info().is_none());
+        assert_eq!(params.screen_info.framebuffer(), Some(fb));
+        assert_eq!(
+            params.e820_regions().collect::<Vec<_>>(),
+            [
+                (0x0, 0x9fc00, E820Type::Ram),
+                (0x9fc00, 0xa0000, E820Type::Reserved),
+                (0x100000, 0x7fe0000, E820Type::Ram),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_e820_type_try_from() {
+        assert_eq!(E820Type::try_from(1).unwrap(), E820Type::Ram);
+        assert_eq!(E820Type::try_from(12).unwrap(), E820Type::Pram);
+        assert_eq!(
+            E820Type::try_from(0xefffffff).unwrap(),
+            E820Type::SoftReserved
+        );
+        assert!(E820Type::try_from(0).is_err());
+        assert!(E820Type::try_from(6).is_err());
+    }
+
+    #[test]
+    fn test_e820_regions() {
+        // SAFETY: All fields of `BootParams` are integers, so zero is a valid value.
+        let mut params: BootParams = unsafe { core::mem::zeroed() };
+        let entries = [
+            (0x0, 0x9fc00, 1),
+            (0x9fc00, 0x400, 2),
+            (0x100000, 0, 1),
+            (0x100000, 0x7ee0000, 1),
+            (0xfeffc000, 0x4000, 0xdead),
+        ];
+        for (i, (addr, size, typ)) in entries.into_iter().enumerate() {
+            params.e820_table[i] = BootE820Entry { addr, size, typ };
+        }
+        params.e820_entries = entries.len() as u8;
+
+        assert_eq!(params.e820_entries().len(), 5);
+        assert!(params.e820_entries()[0].is_usable_ram());
+        assert!(!params.e820_entries()[1].is_usable_ram());
+        assert!(params.e820_entries()[4].e820_type().is_err());
+
+        let regions: Vec<_> = params.e820_regions().collect();
+        assert_eq!(
+            regions,
+            [
+                (0x0, 0x9fc00, E820Type::Ram),
+                (0x9fc00, 0xa0000, E820Type::Reserved),
+                (0x100000, 0x7fe0000, E820Type::Ram),
+                (0xfeffc000, 0xff000000, E820Type::Reserved),
+            ]
+        );
+    }
 }
@@ -18,14 +18,17 @@ mod mapping;
 mod pe_header;
 
 use std::{
+    ffi::{OsStr, OsString},
     fs::File,
     io::{Read, Seek, SeekFrom, Write},
     path::Path,
+    str::FromStr,
 };
 
 use encoder::encode_kernel;
 pub use encoder::PayloadEncoding;
-use mapping::{SetupFileOffset, SetupVA};
+use mapping::{SetupFileOffset, SetupVA, LEGACY_SETUP_SECS, LEGACY_SETUP_SEC_SIZE, SETUP32_LMA};
+use serde::{Deserialize, Serialize};
 use xmas_elf::program::SegmentData;
 
 /// The type of the bzImage that we are building through `make_bzimage`.
@@ -36,6 +39,44 @@ pub enum BzImageType {
     Efi64,
 }
 
+/// The Cargo profile to build the setup binary with.
+///
+/// The setup binary is built in the release profile by default. The debug
+/// profile is useful for debugging the boot path, but it is less stable since
+/// the unoptimized setup code carries more relocations to apply.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SetupProfile {
+    #[default]
+    #[serde(rename = "release")]
+    Release,
+    #[serde(rename = "debug")]
+    Debug,
+}
+
+impl FromStr for SetupProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "release" => Ok(Self::Release),
+            "debug" => Ok(Self::Debug),
+            _ => Err(format!("Invalid setup profile: {}", s)),
+        }
+    }
+}
+
+impl From<OsString> for SetupProfile {
+    fn from(os_string: OsString) -> Self {
+        SetupProfile::from_str(&os_string.to_string_lossy()).unwrap()
+    }
+}
+
+impl From<&OsStr> for SetupProfile {
+    fn from(os_str: &OsStr) -> Self {
+        SetupProfile::from_str(&os_str.to_string_lossy()).unwrap()
+    }
+}
+
 /// Making a bzImage given the kernel ELF and setup source.
 ///
 /// Explanations for the arguments:
@@ -99,6 +140,89 @@ pub fn make_bzimage(
             .unwrap();
         kernel_image.write_all(&pe_header.relocs.1).unwrap();
     }
+
+    if let Err(err) = validate_bzimage(target_image_path) {
+        panic!("The built bzImage is invalid: {}", err);
+    }
+}
+
+/// Validates a bzImage built by `make_bzimage` against the constraints of the
+/// Linux x86 boot loaders.
+///
+/// The following properties are checked:
+///  - the boot signature `0xAA55` is present at offset `0x1fe`;
+///  - the setup header fits in the setup sectors indicated by `setup_sects`;
+///  - the payload described by `payload_offset` and `payload_length` lies
+///    within the image.
+pub fn validate_bzimage(image_path: &Path) -> Result<(), String> {
+    let mut image = Vec::new();
+    File::open(image_path)
+        .and_then(|mut file| file.read_to_end(&mut image))
+        .map_err(|err| format!("failed to read {}: {}", image_path.display(), err))?;
+    validate_bzimage_bytes(&image)
+}
+
+fn validate_bzimage_bytes(image: &[u8]) -> Result<(), String> {
+    let read_u8 = |offset: usize| image.get(offset).copied();
+    let read_u16 = |offset: usize| {
+        let bytes = image.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let read_u32 = |offset: usize| {
+        let bytes = image.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let truncated = || format!("the image is truncated to {:#x} bytes", image.len());
+
+    let boot_flag = read_u16(0x1fe /* boot_flag */).ok_or_else(truncated)?;
+    if boot_flag != 0xAA55 {
+        return Err(format!(
+            "bad boot signature {:#x} at offset 0x1fe, expected 0xaa55",
+            boot_flag
+        ));
+    }
+
+    // The byte at 0x201 holds the distance from itself to the end of the setup header.
+    let header_end = 0x201 + read_u8(0x201 /* jump_addr */).ok_or_else(truncated)? as usize;
+    if image.len() < header_end {
+        return Err(truncated());
+    }
+
+    let setup_sects = read_u8(0x1f1 /* setup_sects */).ok_or_else(truncated)? as usize;
+    let setup_size = 0x200 * (setup_sects + 1);
+    if setup_size < header_end {
+        return Err(format!(
+            "the setup header ends at {:#x}, beyond the {} setup sectors",
+            header_end, setup_sects
+        ));
+    }
+    if setup_sects != LEGACY_SETUP_SECS {
+        return Err(format!(
+            "{} setup sectors are declared, expected {}",
+            setup_sects, LEGACY_SETUP_SECS
+        ));
+    }
+
+    let payload_offset = read_u32(0x248 /* payload_offset */).ok_or_else(truncated)? as usize;
+    let payload_length = read_u32(0x24C /* payload_length */).ok_or_else(truncated)? as usize;
+    if payload_offset < SETUP32_LMA {
+        return Err(format!(
+            "the payload offset {:#x} is below the protected-mode code",
+            payload_offset
+        ));
+    }
+    let payload_start = usize::from(SetupFileOffset::from(SetupVA::from(payload_offset)));
+    let payload_end = payload_start + payload_length;
+    if payload_start < LEGACY_SETUP_SEC_SIZE || payload_end > image.len() {
+        return Err(format!(
+            "the payload at [{:#x}, {:#x}) exceeds the image of {:#x} bytes",
+            payload_start,
+            payload_end,
+            image.len()
+        ));
+    }
+
+    Ok(())
 }
 
 /// To build the legacy32 bzImage setup header, the OSDK should use this target.
@@ -113,6 +237,19 @@ pub fn legacy32_rust_target_json() -> &'static str {
 /// Interestingly, the resulting binary should be the same as the memory
 /// dump of the kernel setup header when it's loaded by the bootloader.
 fn to_flat_binary(elf_file: &[u8]) -> Vec<u8> {
+    elf_to_flat_binary(elf_file, |va| {
+        usize::from(SetupFileOffset::from(SetupVA::from(va)))
+    })
+}
+
+/// Lays out the loadable segments of an ELF file in a flat binary.
+///
+/// Each `PT_LOAD` segment is copied to the file offset that `va_to_file_offset`
+/// maps its virtual address to. As long as the mapping is a constant shift,
+/// the resulting binary satisfies VA delta == File offset delta. Gaps between
+/// the segments are filled with zeros, and the binary is extended whenever a
+/// segment ends beyond it.
+pub fn elf_to_flat_binary(elf_file: &[u8], va_to_file_offset: impl Fn(usize) -> usize) -> Vec<u8> {
     let elf = xmas_elf::ElfFile::new(elf_file).unwrap();
     let mut bin = Vec::<u8>::new();
 
@@ -121,9 +258,7 @@ fn to_flat_binary(elf_file: &[u8]) -> Vec<u8> {
             let SegmentData::Undefined(header_data) = program.get_data(&elf).unwrap() else {
                 panic!("Unexpected segment data type");
             };
-            let dst_file_offset = usize::from(SetupFileOffset::from(SetupVA::from(
-                program.virtual_addr() as usize,
-            )));
+            let dst_file_offset = va_to_file_offset(program.virtual_addr() as usize);
             let dst_file_length = program.file_size() as usize;
             if bin.len() < dst_file_offset + dst_file_length {
                 bin.resize(dst_file_offset + dst_file_length, 0);
@@ -176,3 +311,114 @@ fn fill_legacy_header_fields(
         &((setup_len + kernel_len) as u32).to_le_bytes(),
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds a synthetic image with a minimal setup header of `setup_len`
+    /// bytes, followed by a payload of `payload_len` bytes.
+    fn synthetic_image(setup_len: usize, payload_len: usize) -> Vec<u8> {
+        let mut image = vec![0u8; setup_len + payload_len];
+        image[0x1f1] = LEGACY_SETUP_SECS as u8;
+        image[0x1fe..0x200].copy_from_slice(&0xAA55u16.to_le_bytes());
+        image[0x200] = 0xeb;
+        image[0x201] = 0x63;
+        let payload_offset = usize::from(SetupVA::from(SetupFileOffset::from(setup_len)));
+        image[0x248..0x24c].copy_from_slice(&(payload_offset as u32).to_le_bytes());
+        image[0x24c..0x250].copy_from_slice(&(payload_len as u32).to_le_bytes());
+        image
+    }
+
+    /// Builds a 64-bit ELF file with a `PT_LOAD` segment for each of the
+    /// given virtual addresses and contents.
+    fn synthetic_elf(segments: &[(u64, &[u8])]) -> Vec<u8> {
+        const EHDR_SIZE: usize = 64;
+        const PHDR_SIZE: usize = 56;
+
+        let mut elf = vec![0u8; EHDR_SIZE];
+        elf[0..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2; // ELFCLASS64
+        elf[5] = 1; // ELFDATA2LSB
+        elf[6] = 1; // EV_CURRENT
+        elf[16..18].copy_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        elf[18..20].copy_from_slice(&0x3eu16.to_le_bytes()); // EM_X86_64
+        elf[20..24].copy_from_slice(&1u32.to_le_bytes());
+        elf[32..40].copy_from_slice(&(EHDR_SIZE as u64).to_le_bytes()); // e_phoff
+        elf[52..54].copy_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+        elf[54..56].copy_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+        elf[56..58].copy_from_slice(&(segments.len() as u16).to_le_bytes());
+
+        let mut data_offset = EHDR_SIZE + PHDR_SIZE * segments.len();
+        for (vaddr, data) in segments {
+            let mut phdr = vec![0u8; PHDR_SIZE];
+            phdr[0..4].copy_from_slice(&1u32.to_le_bytes()); // PT_LOAD
+            phdr[8..16].copy_from_slice(&(data_offset as u64).to_le_bytes());
+            phdr[16..24].copy_from_slice(&vaddr.to_le_bytes());
+            phdr[24..32].copy_from_slice(&vaddr.to_le_bytes());
+            phdr[32..40].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            phdr[40..48].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            elf.extend_from_slice(&phdr);
+            data_offset += data.len();
+        }
+        for (_, data) in segments {
+            elf.extend_from_slice(data);
+        }
+        elf
+    }
+
+    #[test]
+    fn flatten_two_segments() {
+        let elf = synthetic_elf(&[(0x1000, &[1, 2, 3, 4]), (0x1008, &[5, 6])]);
+        let bin = elf_to_flat_binary(&elf, |va| va - 0x1000);
+        assert_eq!(bin, [1, 2, 3, 4, 0, 0, 0, 0, 5, 6]);
+    }
+
+    #[test]
+    fn flatten_overlapping_segments() {
+        let elf = synthetic_elf(&[(0x1004, &[5, 6]), (0x1000, &[1, 2, 3, 4, 7])]);
+        let bin = elf_to_flat_binary(&elf, |va| va - 0x1000);
+        assert_eq!(bin, [1, 2, 3, 4, 7, 6]);
+    }
+
+    #[test]
+    fn valid_image() {
+        let image = synthetic_image(LEGACY_SETUP_SEC_SIZE + 0x100, 0x80);
+        assert_eq!(validate_bzimage_bytes(&image), Ok(()));
+    }
+
+    #[test]
+    fn bad_boot_signature() {
+        let mut image = synthetic_image(LEGACY_SETUP_SEC_SIZE, 0x80);
+        image[0x1fe] = 0;
+        assert!(validate_bzimage_bytes(&image)
+            .unwrap_err()
+            .contains("boot signature"));
+    }
+
+    #[test]
+    fn header_beyond_setup_sectors() {
+        let mut image = synthetic_image(LEGACY_SETUP_SEC_SIZE, 0x80);
+        image[0x1f1] = 0;
+        assert!(validate_bzimage_bytes(&image)
+            .unwrap_err()
+            .contains("beyond the 0 setup sectors"));
+    }
+
+    #[test]
+    fn payload_out_of_image() {
+        let mut image = synthetic_image(LEGACY_SETUP_SEC_SIZE, 0x80);
+        image.truncate(image.len() - 1);
+        assert!(validate_bzimage_bytes(&image)
+            .unwrap_err()
+            .contains("exceeds the image"));
+    }
+
+    #[test]
+    fn truncated_image() {
+        let image = vec![0u8; 0x100];
+        assert!(validate_bzimage_bytes(&image)
+            .unwrap_err()
+            .contains("truncated"));
+    }
+}
@@ -143,28 +143,27 @@ fn efi_phase_runtime(memory_map: MemoryMapOwned, boot_params: &mut BootParams) -
             }
             break;
         }
+        let typ = match md.ty {
+            uefi::table::boot::MemoryType::CONVENTIONAL => linux_boot_params::E820Type::Ram,
+            uefi::table::boot::MemoryType::RESERVED => linux_boot_params::E820Type::Reserved,
+            uefi::table::boot::MemoryType::ACPI_RECLAIM => linux_boot_params::E820Type::Acpi,
+            uefi::table::boot::MemoryType::ACPI_NON_VOLATILE => linux_boot_params::E820Type::Nvs,
+            #[cfg(feature = "cvm_guest")]
+            uefi::table::boot::MemoryType::UNACCEPTED => {
+                unsafe {
+                    for page_idx in 0..md.page_count {
+                        tdx_guest::tdcall::accept_page(0, md.phys_start + page_idx * PAGE_SIZE)
+                            .unwrap();
+                    }
+                };
+                linux_boot_params::E820Type::Ram
+            }
+            _ => linux_boot_params::E820Type::Unusable,
+        };
         e820_table[e820_entries] = linux_boot_params::BootE820Entry {
             addr: md.phys_start,
             size: md.page_count * PAGE_SIZE,
-            typ: match md.ty {
-                uefi::table::boot::MemoryType::CONVENTIONAL => linux_boot_params::E820Type::Ram,
-                uefi::table::boot::MemoryType::RESERVED => linux_boot_params::E820Type::Reserved,
-                uefi::table::boot::MemoryType::ACPI_RECLAIM => linux_boot_params::E820Type::Acpi,
-                uefi::table::boot::MemoryType::ACPI_NON_VOLATILE => {
-                    linux_boot_params::E820Type::Nvs
-                }
-                #[cfg(feature = "cvm_guest")]
-                uefi::table::boot::MemoryType::UNACCEPTED => {
-                    unsafe {
-                        for page_idx in 0..md.page_count {
-                            tdx_guest::tdcall::accept_page(0, md.phys_start + page_idx * PAGE_SIZE)
-                                .unwrap();
-                        }
-                    };
-                    linux_boot_params::E820Type::Ram
-                }
-                _ => linux_boot_params::E820Type::Unusable,
-            },
+            typ: typ as u32,
         };
         e820_entries += 1;
     }
@@ -209,7 +208,7 @@ fn efi_phase_runtime(memory_map: MemoryMapOwned, boot_params: &mut BootParams) -
     e820_table[e820_entries] = linux_boot_params::BootE820Entry {
         addr: 0x4000000,
         size: creator.nr_frames_used() as u64 * PAGE_SIZE,
-        typ: linux_boot_params::E820Type::Reserved,
+        typ: linux_boot_params::E820Type::Reserved as u32,
     };
     e820_entries += 1;
     boot_params.e820_entries = e820_entries as u8;
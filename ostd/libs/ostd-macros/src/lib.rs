@@ -135,8 +135,36 @@ pub fn test_panic_handler(_attr: TokenStream, item: TokenStream) -> TokenStream
 ///     assert_eq!(1 + 1, 2);
 /// }
 /// ```
+///
+/// A timeout in milliseconds can be given to catch a test that hangs.
+///
+/// ```ignore
+/// use ostd::prelude::*;
+///
+/// #[ktest(timeout_ms = 1000)]
+/// fn test_fn() {
+///     assert_eq!(1 + 1, 2);
+/// }
+/// ```
 #[proc_macro_attribute]
-pub fn ktest(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn ktest(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let timeout_ms = if attr.is_empty() {
+        None
+    } else {
+        let attr_err_message =
+            "`ktest` attribute should have the format of `timeout_ms = <milliseconds>`";
+        let attr = parse_macro_input!(attr as syn::MetaNameValue);
+        assert!(attr.path.is_ident("timeout_ms"), "{}", attr_err_message);
+        let Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(timeout_ms),
+            ..
+        }) = attr.value
+        else {
+            panic!("{}", attr_err_message);
+        };
+        Some(timeout_ms.base10_parse::<u64>().expect(attr_err_message))
+    };
+
     // Assuming that the item has type `fn() -> ()`, otherwise panics.
     let input = parse_macro_input!(item as ItemFn);
     assert!(
@@ -148,28 +176,279 @@ pub fn ktest(_attr: TokenStream, item: TokenStream) -> TokenStream {
         "ostd::test function should return `()`"
     );
 
-    // Generate a random identifier to avoid name conflicts.
-    let fn_id: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(8)
-        .map(char::from)
-        .collect();
-
     let fn_name = &input.sig.ident;
     let fn_ktest_item_name = Ident::new(
-        &format!("{}_ktest_item_{}", &input.sig.ident, &fn_id),
+        &format!("{}_ktest_item_{}", fn_name, random_fn_id()),
+        proc_macro2::Span::call_site(),
+    );
+    let register_ktest_item = register_ktest_item(
+        &fn_ktest_item_name,
+        fn_name,
+        &fn_name.to_string(),
+        &input.attrs,
+        timeout_ms,
+    );
+
+    let output = quote! {
+        #input
+
+        #register_ktest_item
+    };
+
+    TokenStream::from(output)
+}
+
+/// The test attribute macro to mark a parameterized test function.
+///
+/// The function should take exactly one argument. One test is registered for
+/// each expression in `cases`, named after the function with a `_case_<N>`
+/// suffix, where `N` is the index of the case in the list. The
+/// `#[should_panic]` and `#[ignore]` attributes, if present, apply to every
+/// case.
+///
+/// # Example
+///
+/// ```ignore
+/// use ostd::prelude::*;
+///
+/// #[ktest_each(cases = [1, 2, 3])]
+/// fn test_fn(n: u32) {
+///     assert_eq!(n * 2, n + n);
+/// }
+/// ```
+///
+/// The above registers three tests, `test_fn_case_0`, `test_fn_case_1` and
+/// `test_fn_case_2`.
+#[proc_macro_attribute]
+pub fn ktest_each(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr_err_message =
+        "`ktest_each` attribute should have the format of `cases = [<case>, <case>, ...]`";
+    let attr = parse_macro_input!(attr as syn::MetaNameValue);
+    assert!(attr.path.is_ident("cases"), "{}", attr_err_message);
+    let Expr::Array(cases) = attr.value else {
+        panic!("{}", attr_err_message);
+    };
+    assert!(
+        !cases.elems.is_empty(),
+        "`ktest_each` attribute should have at least one case"
+    );
+
+    let input = parse_macro_input!(item as ItemFn);
+    assert!(
+        input.sig.inputs.len() == 1,
+        "ostd::test function with cases should have exactly one argument"
+    );
+    assert!(
+        matches!(input.sig.output, syn::ReturnType::Default),
+        "ostd::test function should return `()`"
+    );
+
+    let fn_id = random_fn_id();
+    let fn_name = &input.sig.ident;
+    let cases = cases.elems.iter().enumerate().map(|(i, case)| {
+        let case_fn_name = Ident::new(
+            &format!("{}_ktest_case_{}_{}", fn_name, i, &fn_id),
+            proc_macro2::Span::call_site(),
+        );
+        let case_ktest_item_name = Ident::new(
+            &format!("{}_ktest_item_{}_{}", fn_name, i, &fn_id),
+            proc_macro2::Span::call_site(),
+        );
+        let register_ktest_item = register_ktest_item(
+            &case_ktest_item_name,
+            &case_fn_name,
+            &format!("{}_case_{}", fn_name, i),
+            &input.attrs,
+            None,
+        );
+        quote! {
+            #[cfg(ktest)]
+            fn #case_fn_name() {
+                #fn_name(#case);
+            }
+
+            #register_ktest_item
+        }
+    });
+
+    let output = quote! {
+        #input
+
+        #(#cases)*
+    };
+
+    TokenStream::from(output)
+}
+
+/// The attribute macro to mark a function that runs before each test in the
+/// same module.
+///
+/// The setup hook does not apply to the tests in submodules. If it panics, the
+/// test is not run and is reported as failed.
+///
+/// # Example
+///
+/// ```ignore
+/// use ostd::prelude::*;
+///
+/// #[ktest_setup]
+/// fn setup() {
+///     COUNTER.store(0, Ordering::Relaxed);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn ktest_setup(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    register_ktest_hook(item, quote! { Setup })
+}
+
+/// The attribute macro to mark a function that runs after each test in the
+/// same module.
+///
+/// The teardown hook runs even if the test or the setup hook panics. It does
+/// not apply to the tests in submodules.
+///
+/// # Example
+///
+/// ```ignore
+/// use ostd::prelude::*;
+///
+/// #[ktest_teardown]
+/// fn teardown() {
+///     COUNTER.store(0, Ordering::Relaxed);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn ktest_teardown(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    register_ktest_hook(item, quote! { Teardown })
+}
+
+/// Generates the static `KtestHook` of the given kind for the hook function,
+/// in the `.ktest_hook_array` section.
+fn register_ktest_hook(item: TokenStream, kind: proc_macro2::TokenStream) -> TokenStream {
+    // Assuming that the item has type `fn() -> ()`, otherwise panics.
+    let input = parse_macro_input!(item as ItemFn);
+    assert!(
+        input.sig.inputs.is_empty(),
+        "ostd::test hook function should have no arguments"
+    );
+    assert!(
+        matches!(input.sig.output, syn::ReturnType::Default),
+        "ostd::test hook function should return `()`"
+    );
+
+    let fn_name = &input.sig.ident;
+    let hook_name = Ident::new(
+        &format!("{}_ktest_hook_{}", fn_name, random_fn_id()),
         proc_macro2::Span::call_site(),
     );
+    let ktest_crate = ktest_crate();
+
+    let output = quote! {
+        #input
+
+        #[cfg(ktest)]
+        #[used]
+        #[link_section = ".ktest_hook_array"]
+        static #hook_name: #ktest_crate::KtestHook = #ktest_crate::KtestHook::new(
+            #fn_name,
+            #ktest_crate::KtestHookKind::#kind,
+            module_path!(),
+        );
+    };
+
+    TokenStream::from(output)
+}
+
+/// Returns the path to the `ostd-test` crate from the crate being compiled.
+fn ktest_crate() -> proc_macro2::TokenStream {
+    if std::env::var("CARGO_PKG_NAME").unwrap() == "ostd" {
+        quote! { ostd_test }
+    } else {
+        quote! { ostd::ktest }
+    }
+}
+
+/// Generates a random identifier to avoid name conflicts.
+fn random_fn_id() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect()
+}
+
+/// Generates the static `KtestItem` that registers `fn_ident` as a test
+/// named `fn_name`, in the `.ktest_array` section.
+fn register_ktest_item(
+    item_ident: &Ident,
+    fn_ident: &Ident,
+    fn_name: &str,
+    attrs: &[syn::Attribute],
+    timeout_ms: Option<u64>,
+) -> proc_macro2::TokenStream {
+    let (should_panic, expectation) = parse_should_panic(attrs);
+    let ignored = attrs.iter().any(|attr| attr.path().is_ident("ignore"));
+    let expectation_tokens = if let Some(s) = expectation {
+        quote! {
+            Some(#s)
+        }
+    } else {
+        quote! {
+            None
+        }
+    };
+    let timeout_ms_tokens = if let Some(timeout_ms) = timeout_ms {
+        quote! {
+            Some(#timeout_ms)
+        }
+    } else {
+        quote! {
+            None
+        }
+    };
+
+    let package_name = std::env::var("CARGO_PKG_NAME").unwrap();
+    let span = proc_macro::Span::call_site();
+    let source = span.source_file().path();
+    let source = source.to_str().unwrap();
+    let line = span.line();
+    let col = span.column();
 
+    let ktest_crate = ktest_crate();
+
+    quote! {
+        #[cfg(ktest)]
+        #[used]
+        #[link_section = ".ktest_array"]
+        static #item_ident: #ktest_crate::KtestItem = #ktest_crate::KtestItem::new(
+            #fn_ident,
+            (#should_panic, #expectation_tokens),
+            #ignored,
+            #timeout_ms_tokens,
+            #ktest_crate::KtestItemInfo {
+                module_path: module_path!(),
+                fn_name: #fn_name,
+                package: #package_name,
+                source: #source,
+                line: #line,
+                col: #col,
+            },
+        );
+    }
+}
+
+/// Parses the `#[should_panic]` attribute, returning whether the test should
+/// panic and the expected panic message, if any.
+fn parse_should_panic(attrs: &[syn::Attribute]) -> (bool, Option<syn::LitStr>) {
     let is_should_panic_attr = |attr: &&syn::Attribute| {
         attr.path()
             .segments
             .iter()
             .any(|segment| segment.ident == "should_panic")
     };
-    let mut attr_iter = input.attrs.iter();
+    let mut attr_iter = attrs.iter();
     let should_panic = attr_iter.find(is_should_panic_attr);
-    let (should_panic, expectation) = match should_panic {
+    match should_panic {
         Some(attr) => {
             assert!(
                 !attr_iter.any(|attr: &syn::Attribute| is_should_panic_attr(&attr)),
@@ -192,67 +471,5 @@ pub fn ktest(_attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
         None => (false, None),
-    };
-    let expectation_tokens = if let Some(s) = expectation {
-        quote! {
-            Some(#s)
-        }
-    } else {
-        quote! {
-            None
-        }
-    };
-
-    let package_name = std::env::var("CARGO_PKG_NAME").unwrap();
-    let span = proc_macro::Span::call_site();
-    let source = span.source_file().path();
-    let source = source.to_str().unwrap();
-    let line = span.line();
-    let col = span.column();
-
-    let register_ktest_item = if package_name.as_str() == "ostd" {
-        quote! {
-            #[cfg(ktest)]
-            #[used]
-            #[link_section = ".ktest_array"]
-            static #fn_ktest_item_name: ostd_test::KtestItem = ostd_test::KtestItem::new(
-                #fn_name,
-                (#should_panic, #expectation_tokens),
-                ostd_test::KtestItemInfo {
-                    module_path: module_path!(),
-                    fn_name: stringify!(#fn_name),
-                    package: #package_name,
-                    source: #source,
-                    line: #line,
-                    col: #col,
-                },
-            );
-        }
-    } else {
-        quote! {
-            #[cfg(ktest)]
-            #[used]
-            #[link_section = ".ktest_array"]
-            static #fn_ktest_item_name: ostd::ktest::KtestItem = ostd::ktest::KtestItem::new(
-                #fn_name,
-                (#should_panic, #expectation_tokens),
-                ostd::ktest::KtestItemInfo {
-                    module_path: module_path!(),
-                    fn_name: stringify!(#fn_name),
-                    package: #package_name,
-                    source: #source,
-                    line: #line,
-                    col: #col,
-                },
-            );
-        }
-    };
-
-    let output = quote! {
-        #input
-
-        #register_ktest_item
-    };
-
-    TokenStream::from(output)
+    }
 }
@@ -10,7 +10,7 @@
 //! feature provided by the frame including the heap allocator, etc.
 //!
 //! By all means, ostd-test is an individual crate that only requires:
-//!  - a custom linker script section `.ktest_array`,
+//!  - custom linker script sections `.ktest_array` and `.ktest_hook_array`,
 //!  - and an alloc implementation.
 //!
 //! And the OSTD happens to provide both of them. Thus, any crates depending
@@ -60,6 +60,57 @@
 //! We support the `#[should_panic]` attribute just in the same way as the standard
 //! library do, but the implementation is quite slow currently. Use it with cautious.
 //!
+//! The `#[ignore]` attribute is supported as well. Ignored tests are skipped by
+//! the runner and counted separately, unless OSDK is asked to include them with
+//! `cargo osdk test --include-ignored`.
+//!
+//! A test that may hang can be given a timeout in milliseconds with
+//! `#[ktest(timeout_ms = 1000)]`. If the test does not finish in time, the
+//! runner reports it as timed out and aborts the test run, rather than hanging
+//! without telling which test is stuck. Tests without a timeout may run for
+//! arbitrarily long.
+//!
+//! Setup and teardown code shared by the tests in a module can be written as
+//! hooks with the `#[ktest_setup]` and `#[ktest_teardown]` attributes. The
+//! setup hook runs before each test in the same module, and the teardown hook
+//! runs after each of them, even if the test panics. Hooks are not inherited
+//! by the tests in submodules.
+//!
+//! ```rust
+//! #[cfg(ktest)]
+//! mod test {
+//!     use ostd::prelude::*;
+//!
+//!     #[ktest_setup]
+//!     fn setup() {
+//!         // Prepares the state that every test in this module expects.
+//!     }
+//!
+//!     #[ktest_teardown]
+//!     fn teardown() {
+//!         // Cleans up the state, whether the test passed or not.
+//!     }
+//! }
+//! ```
+//!
+//! Table-driven tests can be written with the `#[ktest_each]` attribute, which
+//! registers one test per case. The test function takes a single argument:
+//!
+//! ```rust
+//! #[cfg(ktest)]
+//! mod test {
+//!     use ostd::prelude::*;
+//!
+//!     #[ktest_each(cases = [1, 2, 3])]
+//!     fn double(n: u32) {
+//!         assert_eq!(n * 2, n + n);
+//!     }
+//! }
+//! ```
+//!
+//! The generated tests are named `double_case_0`, `double_case_1` and
+//! `double_case_2`.
+//!
 //! Doctest is not taken into consideration yet, and the interface is subject to
 //! change.
 //!
@@ -90,6 +141,8 @@ pub enum KtestError {
     Panic(Box<PanicInfo>),
     ShouldPanicButNoPanic,
     ExpectedPanicNotMatch(&'static str, Box<PanicInfo>),
+    /// The test did not finish within the timeout in milliseconds.
+    Timeout(u64),
     Unknown,
 }
 
@@ -116,6 +169,8 @@ pub struct KtestItemInfo {
 pub struct KtestItem {
     fn_: fn() -> (),
     should_panic: (bool, Option<&'static str>),
+    ignored: bool,
+    timeout_ms: Option<u64>,
     info: KtestItemInfo,
 }
 
@@ -130,11 +185,15 @@ impl KtestItem {
     pub const fn new(
         fn_: fn() -> (),
         should_panic: (bool, Option<&'static str>),
+        ignored: bool,
+        timeout_ms: Option<u64>,
         info: KtestItemInfo,
     ) -> Self {
         Self {
             fn_,
             should_panic,
+            ignored,
+            timeout_ms,
             info,
         }
     }
@@ -144,18 +203,28 @@ impl KtestItem {
         &self.info
     }
 
+    /// Returns whether the test is marked with `#[ignore]`.
+    ///
+    /// Ignored tests should be skipped by the runner unless explicitly
+    /// requested. See [`get_ktest_run_ignored`].
+    pub fn is_ignored(&self) -> bool {
+        self.ignored
+    }
+
+    /// Returns the timeout of the test in milliseconds, if any.
+    ///
+    /// The timeout is given by `#[ktest(timeout_ms = N)]`. It is up to the
+    /// runner to enforce the timeout.
+    pub fn timeout_ms(&self) -> Option<u64> {
+        self.timeout_ms
+    }
+
     /// Run the test with a given catch_unwind implementation.
     pub fn run(&self, catch_unwind_impl: &CatchUnwindImpl) -> Result<(), KtestError> {
         let test_result = catch_unwind_impl(self.fn_);
         if !self.should_panic.0 {
             // Should not panic.
-            match test_result {
-                Ok(()) => Ok(()),
-                Err(e) => match e.downcast::<PanicInfo>() {
-                    Ok(s) => Err(KtestError::Panic(s)),
-                    Err(_payload) => Err(KtestError::Unknown),
-                },
-            }
+            test_result.map_err(panic_error)
         } else {
             // Should panic.
             match test_result {
@@ -179,6 +248,61 @@ impl KtestItem {
     }
 }
 
+/// Converts the payload of a caught panic to a [`KtestError`].
+fn panic_error(payload: Box<dyn core::any::Any + Send>) -> KtestError {
+    match payload.downcast::<PanicInfo>() {
+        Ok(s) => KtestError::Panic(s),
+        Err(_payload) => KtestError::Unknown,
+    }
+}
+
+/// The kind of a [`KtestHook`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KtestHookKind {
+    /// Runs before each test in the module (marked with `#[ktest_setup]`).
+    Setup,
+    /// Runs after each test in the module (marked with `#[ktest_teardown]`).
+    Teardown,
+}
+
+/// A hook function that runs around each test in a module.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KtestHook {
+    fn_: fn() -> (),
+    kind: KtestHookKind,
+    module_path: &'static str,
+}
+
+impl KtestHook {
+    /// Create a new [`KtestHook`].
+    ///
+    /// Do not use this function directly. Instead, use the `#[ktest_setup]`
+    /// or `#[ktest_teardown]` attribute to mark the hook function.
+    #[doc(hidden)]
+    pub const fn new(fn_: fn() -> (), kind: KtestHookKind, module_path: &'static str) -> Self {
+        Self {
+            fn_,
+            kind,
+            module_path,
+        }
+    }
+
+    /// Returns the kind of the hook.
+    pub fn kind(&self) -> KtestHookKind {
+        self.kind
+    }
+
+    /// Returns the path of the module whose tests the hook applies to.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// Run the hook with a given catch_unwind implementation.
+    pub fn run(&self, catch_unwind_impl: &CatchUnwindImpl) -> Result<(), KtestError> {
+        catch_unwind_impl(self.fn_).map_err(panic_error)
+    }
+}
+
 macro_rules! ktest_array {
     () => {{
         extern "C" {
@@ -222,11 +346,59 @@ impl core::iter::Iterator for KtestIter {
     }
 }
 
+macro_rules! ktest_hook_array {
+    () => {{
+        extern "C" {
+            fn __ktest_hook_array();
+            fn __ktest_hook_array_end();
+        }
+        let item_size = core::mem::size_of::<KtestHook>();
+        let l = (__ktest_hook_array_end as usize - __ktest_hook_array as usize) / item_size;
+        // SAFETY: __ktest_hook_array is a static section consisting of KtestHook.
+        unsafe { core::slice::from_raw_parts(__ktest_hook_array as *const KtestHook, l) }
+    }};
+}
+
+/// The iterator of the ktest hook array.
+pub struct KtestHookIter {
+    index: usize,
+}
+
+impl Default for KtestHookIter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KtestHookIter {
+    /// Create a new [`KtestHookIter`].
+    ///
+    /// It will iterate over all the hooks (marked with `#[ktest_setup]` or
+    /// `#[ktest_teardown]`).
+    pub fn new() -> Self {
+        Self { index: 0 }
+    }
+}
+
+impl core::iter::Iterator for KtestHookIter {
+    type Item = KtestHook;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hook = ktest_hook_array!().get(self.index)?;
+        self.index += 1;
+        Some(hook.clone())
+    }
+}
+
 // The whitelists that will be generated by the OSDK as static consts.
 // They deliver the target tests that the user wants to run.
 extern "Rust" {
     static KTEST_TEST_WHITELIST: Option<&'static [&'static str]>;
     static KTEST_CRATE_WHITELIST: Option<&'static [&'static str]>;
+    static KTEST_RUN_IGNORED: bool;
+    static KTEST_OUTPUT_FORMAT: &'static str;
+    static KTEST_ORDER: &'static str;
+    static KTEST_SHUFFLE_SEED: u64;
 }
 
 /// Get the whitelist of the tests.
@@ -234,7 +406,7 @@ extern "Rust" {
 /// The whitelist is generated by the OSDK runner, indicating name of the
 /// target tests that the user wants to run.
 pub fn get_ktest_test_whitelist() -> Option<&'static [&'static str]> {
-    // SAFETY: The two extern statics in the base crate are generated by OSDK.
+    // SAFETY: The extern statics in the base crate are generated by OSDK.
     unsafe { KTEST_TEST_WHITELIST }
 }
 
@@ -243,6 +415,64 @@ pub fn get_ktest_test_whitelist() -> Option<&'static [&'static str]> {
 /// The whitelist is generated by the OSDK runner, indicating the target crate
 /// that the user wants to test.
 pub fn get_ktest_crate_whitelist() -> Option<&'static [&'static str]> {
-    // SAFETY: The two extern statics in the base crate are generated by OSDK.
+    // SAFETY: The extern statics in the base crate are generated by OSDK.
     unsafe { KTEST_CRATE_WHITELIST }
 }
+
+/// Get whether the ignored tests should be run.
+///
+/// The flag is generated by the OSDK runner, indicating that the tests marked
+/// with `#[ignore]` should be run as well.
+pub fn get_ktest_run_ignored() -> bool {
+    // SAFETY: The extern static in the base crate is generated by OSDK.
+    unsafe { KTEST_RUN_IGNORED }
+}
+
+/// The format in which the test runner reports the test results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KtestOutputFormat {
+    /// Colored human-readable output.
+    Pretty,
+    /// The [Test Anything Protocol](https://testanything.org/).
+    Tap,
+    /// A JSON summary printed after all tests have finished.
+    Json,
+}
+
+/// Get the format in which the test results should be reported.
+///
+/// The format is generated by the OSDK runner. Unknown formats fall back to
+/// [`KtestOutputFormat::Pretty`].
+pub fn get_ktest_output_format() -> KtestOutputFormat {
+    // SAFETY: The extern static in the base crate is generated by OSDK.
+    match unsafe { KTEST_OUTPUT_FORMAT } {
+        "tap" => KtestOutputFormat::Tap,
+        "json" => KtestOutputFormat::Json,
+        _ => KtestOutputFormat::Pretty,
+    }
+}
+
+/// The order in which the test runner runs the tests in each crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KtestOrder {
+    /// The order of the tests in the source tree.
+    Default,
+    /// The reverse of the default order.
+    Reverse,
+    /// A pseudo-random order determined by the seed.
+    Shuffle(u64),
+}
+
+/// Get the order in which the tests should be run.
+///
+/// The order and the shuffle seed are generated by the OSDK runner. Unknown
+/// orders fall back to [`KtestOrder::Default`].
+pub fn get_ktest_order() -> KtestOrder {
+    // SAFETY: The extern statics in the base crate are generated by OSDK.
+    match unsafe { KTEST_ORDER } {
+        "reverse" => KtestOrder::Reverse,
+        // SAFETY: The extern statics in the base crate are generated by OSDK.
+        "shuffle" => KtestOrder::Shuffle(unsafe { KTEST_SHUFFLE_SEED }),
+        _ => KtestOrder::Default,
+    }
+}
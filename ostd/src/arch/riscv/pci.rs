@@ -3,6 +3,12 @@
 //! PCI bus io port
 
 use super::device::io_port::{IoPort, ReadWriteAccess, WriteOnlyAccess};
+use crate::bus::pci::CSpaceAccessMethod;
 
 pub static PCI_ADDRESS_PORT: IoPort<u32, WriteOnlyAccess> = unsafe { IoPort::new(0x0) };
 pub static PCI_DATA_PORT: IoPort<u32, ReadWriteAccess> = unsafe { IoPort::new(0x0) };
+
+/// Returns the method to access the PCI configuration space.
+pub(crate) fn cspace_access_method() -> CSpaceAccessMethod {
+    CSpaceAccessMethod::Io
+}
@@ -5,7 +5,7 @@
 
 use core::ffi::CStr;
 
-use linux_boot_params::{BootParams, E820Type, LINUX_BOOT_HEADER_MAGIC};
+use linux_boot_params::{BootParams, E820Type};
 
 use crate::{
     boot::{
@@ -45,14 +45,13 @@ fn parse_bootloader_name(boot_params: &BootParams) -> &str {
 
 fn parse_kernel_commandline(boot_params: &BootParams) -> &str {
     // SAFETY: The pointer in the header points to a valid C string.
-    let cmdline_c_str: &CStr = unsafe { CStr::from_ptr(boot_params.hdr.cmd_line_ptr as *const i8) };
+    let cmdline_c_str: &CStr = unsafe { CStr::from_ptr(boot_params.cmd_line_ptr() as *const i8) };
     let cmdline_str = cmdline_c_str.to_str().unwrap();
     cmdline_str
 }
 
 fn parse_initramfs(boot_params: &BootParams) -> Option<&[u8]> {
-    let hdr = &boot_params.hdr;
-    let ptr = hdr.ramdisk_image as usize;
+    let ptr = boot_params.ramdisk_image() as usize;
     if ptr == 0 {
         return None;
     }
@@ -62,7 +61,7 @@ fn parse_initramfs(boot_params: &BootParams) -> Option<&[u8]> {
     } else {
         ptr
     };
-    let length = hdr.ramdisk_size as usize;
+    let length = boot_params.ramdisk_size() as usize;
     if length == 0 {
         return None;
     }
@@ -71,11 +70,9 @@ fn parse_initramfs(boot_params: &BootParams) -> Option<&[u8]> {
 }
 
 fn parse_acpi_arg(boot_params: &BootParams) -> BootloaderAcpiArg {
-    let rsdp = boot_params.acpi_rsdp_addr;
-    if rsdp == 0 {
-        BootloaderAcpiArg::NotProvided
-    } else {
-        BootloaderAcpiArg::Rsdp(rsdp.try_into().expect("RSDP address overflowed!"))
+    match boot_params.acpi_rsdp() {
+        Some(rsdp) => BootloaderAcpiArg::Rsdp(rsdp.try_into().expect("RSDP address overflowed!")),
+        None => BootloaderAcpiArg::NotProvided,
     }
 }
 
@@ -108,13 +105,14 @@ fn parse_memory_regions(boot_params: &BootParams) -> MemoryRegionArray {
     let mut regions = MemoryRegionArray::new();
 
     // Add regions from E820.
-    let num_entries = boot_params.e820_entries as usize;
-    for e820_entry in &boot_params.e820_table[0..num_entries] {
+    for e820_entry in boot_params.e820_entries() {
         regions
             .push(MemoryRegion::new(
                 e820_entry.addr as usize,
                 e820_entry.size as usize,
-                e820_entry.typ.into(),
+                e820_entry
+                    .e820_type()
+                    .map_or(MemoryRegionType::BadMemory, Into::into),
             ))
             .unwrap();
     }
@@ -125,8 +123,8 @@ fn parse_memory_regions(boot_params: &BootParams) -> MemoryRegionArray {
     // Add the initramfs region.
     regions
         .push(MemoryRegion::new(
-            boot_params.hdr.ramdisk_image as usize,
-            boot_params.hdr.ramdisk_size as usize,
+            boot_params.ramdisk_image() as usize,
+            boot_params.ramdisk_size() as usize,
             MemoryRegionType::Module,
         ))
         .unwrap();
@@ -147,7 +145,7 @@ fn parse_memory_regions(boot_params: &BootParams) -> MemoryRegionArray {
 #[no_mangle]
 unsafe extern "sysv64" fn __linux_boot(params_ptr: *const BootParams) -> ! {
     let params = unsafe { &*params_ptr };
-    assert_eq!({ params.hdr.header }, LINUX_BOOT_HEADER_MAGIC);
+    params.hdr.validate().expect("invalid Linux boot header");
 
     use crate::boot::{call_ostd_main, EarlyBootInfo, EARLY_INFO};
 
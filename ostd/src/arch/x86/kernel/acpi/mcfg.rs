@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use acpi::{
+    sdt::{SdtHeader, Signature},
+    AcpiTable,
+};
+
+use crate::mm::{paddr_to_vaddr, Paddr};
+
+/// PCI Express memory mapped configuration space base address description table.
+///
+/// Each entry of the table describes an ECAM region through which the PCI
+/// configuration space of a range of buses can be accessed.
+#[derive(Debug)]
+pub struct Mcfg {
+    entries: Vec<McfgEntry>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct McfgHeader {
+    header: SdtHeader,
+    _reserved: [u8; 8],
+}
+
+unsafe impl AcpiTable for McfgHeader {
+    const SIGNATURE: Signature = Signature::MCFG;
+    fn header(&self) -> &acpi::sdt::SdtHeader {
+        &self.header
+    }
+}
+
+/// The configuration space base address allocation structure.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct McfgEntry {
+    /// The physical base address of the ECAM region, which corresponds to bus 0
+    /// even if `start_bus` is not 0.
+    pub base_address: u64,
+    pub pci_segment_group: u16,
+    pub start_bus: u8,
+    pub end_bus: u8,
+    _reserved: u32,
+}
+
+impl Mcfg {
+    /// Creates a instance from ACPI table.
+    pub fn new() -> Option<Self> {
+        if !super::ACPI_TABLES.is_completed() {
+            return None;
+        }
+        let acpi_table_lock = super::ACPI_TABLES.get().unwrap().lock();
+        // SAFETY: The McfgHeader is the header for the MCFG structure, it fits all the field described in the PCI Firmware Specification.
+        let mcfg_mapping = acpi_table_lock.find_table::<McfgHeader>().ok()?;
+
+        let physical_address = mcfg_mapping.physical_start() + size_of::<McfgHeader>();
+        let nr_entries =
+            (mcfg_mapping.mapped_length() - size_of::<McfgHeader>()) / size_of::<McfgEntry>();
+        // SAFETY: The target address is the start of the allocation structures, which
+        // are packed and follow the header until the end of the table.
+        let entries = unsafe {
+            core::slice::from_raw_parts(
+                paddr_to_vaddr(physical_address) as *const McfgEntry,
+                nr_entries,
+            )
+        };
+
+        Some(Mcfg {
+            entries: entries.to_vec(),
+        })
+    }
+
+    /// Returns the ECAM region of the given PCI segment group, i.e., its base address
+    /// and the first and the last buses that it covers.
+    pub fn ecam_region(&self, pci_segment_group: u16) -> Option<(Paddr, u8, u8)> {
+        self.entries
+            .iter()
+            .find(|entry| { entry.pci_segment_group } == pci_segment_group)
+            .map(|entry| (entry.base_address as Paddr, entry.start_bus, entry.end_bus))
+    }
+}
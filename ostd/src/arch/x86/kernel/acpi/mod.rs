@@ -3,6 +3,7 @@
 #![allow(unused_variables)]
 
 pub mod dmar;
+pub mod mcfg;
 pub mod remapping;
 
 use core::ptr::NonNull;
@@ -2,7 +2,30 @@
 
 //! PCI bus io port
 
-use super::device::io_port::{IoPort, ReadWriteAccess, WriteOnlyAccess};
+use super::{
+    device::io_port::{IoPort, ReadWriteAccess, WriteOnlyAccess},
+    kernel::acpi::mcfg::Mcfg,
+};
+use crate::bus::pci::CSpaceAccessMethod;
 
 pub static PCI_ADDRESS_PORT: IoPort<u32, WriteOnlyAccess> = unsafe { IoPort::new(0x0CF8) };
 pub static PCI_DATA_PORT: IoPort<u32, ReadWriteAccess> = unsafe { IoPort::new(0x0CFC) };
+
+/// Returns the method to access the PCI configuration space.
+///
+/// The configuration space is accessed through the ECAM region of PCI segment
+/// group 0 if the ACPI MCFG table reports one. Otherwise, it is accessed through
+/// the I/O ports.
+pub(crate) fn cspace_access_method() -> CSpaceAccessMethod {
+    let Some(mcfg) = Mcfg::new() else {
+        return CSpaceAccessMethod::Io;
+    };
+    match mcfg.ecam_region(0) {
+        Some((base, start_bus, end_bus)) => CSpaceAccessMethod::Mmio {
+            base,
+            start_bus,
+            end_bus,
+        },
+        None => CSpaceAccessMethod::Io,
+    }
+}
@@ -4,12 +4,19 @@
 
 #![allow(unused_variables)]
 
-use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use alloc::{
+    collections::{BTreeSet, VecDeque},
+    sync::Arc,
+    vec::Vec,
+};
 use core::fmt::Debug;
 
 use log::{debug, error};
 
-use super::{device_info::PciDeviceId, PciCommonDevice};
+use super::{
+    device_info::{PciDeviceId, PciDeviceLocation},
+    PciCommonDevice,
+};
 use crate::bus::BusProbeError;
 
 /// PciDevice trait.
@@ -43,6 +50,8 @@ pub struct PciBus {
     common_devices: VecDeque<PciCommonDevice>,
     devices: Vec<Arc<dyn PciDevice>>,
     drivers: Vec<Arc<dyn PciDriver>>,
+    /// The locations of all the registered devices, claimed or not.
+    locations: BTreeSet<PciDeviceLocation>,
 }
 
 impl PciBus {
@@ -72,8 +81,19 @@ impl PciBus {
         self.drivers.push(driver);
     }
 
+    /// Calls `f` on each PCI device claimed by a driver.
+    pub fn for_each_claimed_device(&self, f: impl FnMut(&Arc<dyn PciDevice>)) {
+        self.devices.iter().for_each(f);
+    }
+
+    /// Returns a snapshot of the PCI devices claimed by drivers.
+    pub fn collect_claimed_devices(&self) -> Vec<Arc<dyn PciDevice>> {
+        self.devices.clone()
+    }
+
     pub(super) fn register_common_device(&mut self, mut common_device: PciCommonDevice) {
         debug!("Find pci common devices:{:x?}", common_device);
+        self.locations.insert(*common_device.location());
         let device_id = *common_device.device_id();
         for driver in self.drivers.iter() {
             common_device = match driver.probe(common_device) {
@@ -94,11 +114,62 @@ impl PciBus {
         self.common_devices.push_back(common_device);
     }
 
+    /// Returns whether a device at the location has been registered.
+    pub(super) fn is_registered(&self, location: &PciDeviceLocation) -> bool {
+        self.locations.contains(location)
+    }
+
     pub(super) const fn new() -> Self {
         Self {
             common_devices: VecDeque::new(),
             devices: Vec::new(),
             drivers: Vec::new(),
+            locations: BTreeSet::new(),
+        }
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug)]
+    struct MockDevice {
+        device_id: u16,
+    }
+
+    impl PciDevice for MockDevice {
+        fn device_id(&self) -> PciDeviceId {
+            PciDeviceId {
+                vendor_id: 0x1af4,
+                device_id: self.device_id,
+                revision_id: 0,
+                prog_if: 0,
+                subclass: 0,
+                class: 0,
+                subsystem_vendor_id: 0,
+                subsystem_id: 0,
+            }
         }
     }
+
+    #[ktest]
+    fn visit_each_claimed_device_once() {
+        let mut bus = PciBus::new();
+        for device_id in [1, 2, 3] {
+            bus.devices.push(Arc::new(MockDevice { device_id }));
+        }
+
+        let mut visited = Vec::new();
+        bus.for_each_claimed_device(|device| visited.push(device.device_id().device_id));
+        assert_eq!(visited, [1, 2, 3]);
+
+        let devices = bus.collect_claimed_devices();
+        assert_eq!(devices.len(), 3);
+        assert!(devices
+            .iter()
+            .zip(bus.devices.iter())
+            .all(|(a, b)| Arc::ptr_eq(a, b)));
+    }
 }
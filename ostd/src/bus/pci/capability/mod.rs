@@ -8,7 +8,7 @@ use alloc::vec::Vec;
 
 use self::{msix::CapabilityMsixData, vendor::CapabilityVndrData};
 use super::{
-    cfg_space::{PciDeviceCommonCfgOffset, Status},
+    cfg_space::{PciConfigAccess, PciConfigHeader},
     common_device::PciCommonDevice,
     PciDeviceLocation,
 };
@@ -29,6 +29,17 @@ pub struct Capability {
     cap_data: CapabilityData,
 }
 
+/// Errors that occur when parsing the capabilities of a PCI device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityError {
+    /// A capability pointer points into the common configuration space header.
+    InvalidPointer(u16),
+    /// The capability list loops back to the capability at the position.
+    Cycle(u16),
+    /// The BAR used by the MSI-X capability is absent or is not a memory BAR.
+    InvalidMsixBar(u8),
+}
+
 /// PCI Capability data.
 #[derive(Debug, Clone)]
 pub enum CapabilityData {
@@ -79,37 +90,30 @@ pub enum CapabilityData {
 impl Capability {
     /// 0xFC, the top of the capability position.
     const CAPABILITY_TOP: u16 = 0xFC;
+    /// 0x40, the bottom of the capability position, i.e., the end of the common header.
+    const CAPABILITY_BOTTOM: u16 = 0x40;
 
     /// Gets the capability data
     pub fn capability_data(&self) -> &CapabilityData {
         &self.cap_data
     }
 
-    /// Gets the capabilities of one device
-    pub(super) fn device_capabilities(dev: &mut PciCommonDevice) -> Vec<Self> {
-        if !dev.status().contains(Status::CAPABILITIES_LIST) {
-            return Vec::new();
-        }
+    /// Gets the capabilities of one device, whose configuration space is accessed
+    /// through `access`.
+    pub(super) fn device_capabilities(
+        dev: &mut PciCommonDevice,
+        access: impl PciConfigAccess + Copy,
+    ) -> Result<Vec<Self>, CapabilityError> {
         let mut capabilities = Vec::new();
-        let mut cap_ptr =
-            dev.location()
-                .read8(PciDeviceCommonCfgOffset::CapabilitiesPointer as u16) as u16
-                & PciDeviceLocation::BIT32_ALIGN_MASK;
-        let mut cap_ptr_vec = Vec::new();
         // read all cap_ptr so that it is easy for us to get the length.
-        while cap_ptr > 0 {
-            cap_ptr_vec.push(cap_ptr);
-            cap_ptr =
-                dev.location().read8(cap_ptr + 1) as u16 & PciDeviceLocation::BIT32_ALIGN_MASK;
-        }
-        cap_ptr_vec.sort();
+        let mut cap_ptr_vec = Self::capability_pointers(access)?;
         // Push here so that we can calculate the length of the last capability.
         cap_ptr_vec.push(Self::CAPABILITY_TOP);
         let length = cap_ptr_vec.len();
         for i in 0..length - 1 {
             let cap_ptr = cap_ptr_vec[i];
             let next_ptr = cap_ptr_vec[i + 1];
-            let cap_type = dev.location().read8(cap_ptr);
+            let cap_type = access.read8(cap_ptr);
             let data = match cap_type {
                 0x01 => CapabilityData::Pm,
                 0x02 => CapabilityData::Agp,
@@ -129,7 +133,7 @@ impl Capability {
                 0x0E => CapabilityData::Agp3,
                 0x0F => CapabilityData::Secdev,
                 0x10 => CapabilityData::Exp,
-                0x11 => CapabilityData::Msix(CapabilityMsixData::new(dev, cap_ptr)),
+                0x11 => CapabilityData::Msix(CapabilityMsixData::new(dev, access, cap_ptr)?),
                 0x12 => CapabilityData::Sata,
                 0x13 => CapabilityData::Af,
                 0x14 => CapabilityData::Ea,
@@ -143,6 +147,80 @@ impl Capability {
                 cap_data: data,
             });
         }
-        capabilities
+        Ok(capabilities)
+    }
+
+    /// Reads the positions of all the capabilities in the capability list,
+    /// sorted in ascending order.
+    pub(super) fn capability_pointers(
+        access: impl PciConfigAccess + Copy,
+    ) -> Result<Vec<u16>, CapabilityError> {
+        let Some(cap_ptr) = PciConfigHeader::new(access).cap_ptr() else {
+            return Ok(Vec::new());
+        };
+        let mut cap_ptr = cap_ptr as u16;
+        let mut cap_ptr_vec = Vec::new();
+        while cap_ptr > 0 {
+            if cap_ptr < Self::CAPABILITY_BOTTOM {
+                return Err(CapabilityError::InvalidPointer(cap_ptr));
+            }
+            // There are at most 48 capabilities in the 256-byte configuration space,
+            // so this check is cheap and guarantees that the walk terminates.
+            if cap_ptr_vec.contains(&cap_ptr) {
+                return Err(CapabilityError::Cycle(cap_ptr));
+            }
+            cap_ptr_vec.push(cap_ptr);
+            cap_ptr = access.read8(cap_ptr + 1) as u16 & PciDeviceLocation::BIT32_ALIGN_MASK;
+        }
+        cap_ptr_vec.sort();
+        Ok(cap_ptr_vec)
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::vec;
+
+    use super::*;
+    use crate::{
+        bus::pci::{cfg_space::Status, mock::MockConfigSpace},
+        prelude::*,
+    };
+
+    /// Creates a configuration space with the capabilities at `cap_ptrs`,
+    /// where each capability points to the next one and the last one points to `last_next`.
+    fn config_space_with_caps(cap_ptrs: &[u8], last_next: u8) -> MockConfigSpace {
+        let space = MockConfigSpace::new();
+        space.set_bytes(0x06, &Status::CAPABILITIES_LIST.bits().to_le_bytes());
+        space.set_bytes(0x34, &[cap_ptrs[0]]);
+        for (i, cap_ptr) in cap_ptrs.iter().enumerate() {
+            let next = cap_ptrs.get(i + 1).copied().unwrap_or(last_next);
+            space.set_bytes(*cap_ptr as u16, &[0x09, next]);
+        }
+        space
+    }
+
+    #[ktest]
+    fn walk_capability_list() {
+        let space = config_space_with_caps(&[0x60, 0x40, 0x50], 0);
+        assert_eq!(
+            Capability::capability_pointers(&space),
+            Ok(vec![0x40, 0x50, 0x60])
+        );
+    }
+
+    #[ktest]
+    fn detect_capability_cycle() {
+        let space = config_space_with_caps(&[0x40, 0x50, 0x60], 0x50);
+        assert_eq!(
+            Capability::capability_pointers(&space),
+            Err(CapabilityError::Cycle(0x50))
+        );
+
+        let space = config_space_with_caps(&[0x40], 0x40);
+        assert_eq!(
+            Capability::capability_pointers(&space),
+            Err(CapabilityError::Cycle(0x40))
+        );
     }
 }
@@ -12,7 +12,8 @@ use cfg_if::cfg_if;
 use crate::{
     arch::iommu::has_interrupt_remapping,
     bus::pci::{
-        cfg_space::{Bar, Command, MemoryBar},
+        capability::CapabilityError,
+        cfg_space::{Bar, Command, MemoryBar, PciConfigAccess, PciConfigHeader},
         common_device::PciCommonDevice,
         device_info::PciDeviceLocation,
     },
@@ -63,45 +64,60 @@ impl Clone for CapabilityMsixData {
 #[cfg(target_arch = "x86_64")]
 const MSIX_DEFAULT_MSG_ADDR: u32 = 0xFEE0_0000;
 
+/// The MSI-X Enable bit of the Message Control word.
+const MSIX_CTRL_ENABLE_BIT: usize = 15;
+/// The Function Mask bit of the Message Control word.
+const MSIX_CTRL_FUNCTION_MASK_BIT: usize = 14;
+
+/// The size of an MSI-X table entry in bytes.
+const MSIX_ENTRY_SIZE: usize = 16;
+/// The offset of the Vector Control dword in an MSI-X table entry.
+const MSIX_VECTOR_CONTROL_OFFSET: usize = 12;
+/// The Mask bit of the Vector Control dword.
+const MSIX_VECTOR_CONTROL_MASK: u32 = 1;
+
 impl CapabilityMsixData {
-    pub(super) fn new(dev: &mut PciCommonDevice, cap_ptr: u16) -> Self {
+    pub(super) fn new(
+        dev: &mut PciCommonDevice,
+        access: impl PciConfigAccess + Copy,
+        cap_ptr: u16,
+    ) -> Result<Self, CapabilityError> {
         // Get Table and PBA offset, provide functions to modify them
-        let table_info = dev.location().read32(cap_ptr + 4);
-        let pba_info = dev.location().read32(cap_ptr + 8);
+        let table_info = access.read32(cap_ptr + 4);
+        let pba_info = access.read32(cap_ptr + 8);
 
         let table_bar;
         let pba_bar;
 
-        let bar_manager = dev.bar_manager_mut();
-        bar_manager.set_invisible((pba_info & 0b111) as u8);
-        bar_manager.set_invisible((table_info & 0b111) as u8);
-        match bar_manager
-            .bar_space_without_invisible((pba_info & 0b111) as u8)
-            .expect("MSIX cfg:pba BAR is none")
+        let pba_bar_idx = (pba_info & 0b111) as u8;
+        let table_bar_idx = (table_info & 0b111) as u8;
+        // The BAR indicator can only refer to BAR 0-5.
+        if let Some(idx) = [pba_bar_idx, table_bar_idx]
+            .into_iter()
+            .find(|idx| *idx > 5)
         {
-            Bar::Memory(memory) => {
+            return Err(CapabilityError::InvalidMsixBar(idx));
+        }
+        let bar_manager = dev.bar_manager_mut();
+        bar_manager.set_invisible(pba_bar_idx);
+        bar_manager.set_invisible(table_bar_idx);
+        match bar_manager.bar_space_without_invisible(pba_bar_idx) {
+            Some(Bar::Memory(memory)) => {
                 pba_bar = memory;
             }
-            Bar::Io(_) => {
-                panic!("MSIX cfg:pba BAR is IO type")
-            }
+            _ => return Err(CapabilityError::InvalidMsixBar(pba_bar_idx)),
         };
-        match bar_manager
-            .bar_space_without_invisible((table_info & 0b111) as u8)
-            .expect("MSIX cfg:table BAR is none")
-        {
-            Bar::Memory(memory) => {
+        match bar_manager.bar_space_without_invisible(table_bar_idx) {
+            Some(Bar::Memory(memory)) => {
                 table_bar = memory;
             }
-            Bar::Io(_) => {
-                panic!("MSIX cfg:table BAR is IO type")
-            }
+            _ => return Err(CapabilityError::InvalidMsixBar(table_bar_idx)),
         }
 
         let pba_offset = (pba_info & !(0b111u32)) as usize;
         let table_offset = (table_info & !(0b111u32)) as usize;
 
-        let table_size = (dev.location().read16(cap_ptr + 2) & 0b11_1111_1111) + 1;
+        let table_size = (access.read16(cap_ptr + 2) & 0b11_1111_1111) + 1;
         // TODO: Different architecture seems to have different, so we should set different address here.
         let message_address = MSIX_DEFAULT_MSG_ADDR;
         let message_upper_address = 0u32;
@@ -138,26 +154,26 @@ impl CapabilityMsixData {
         }
 
         // enable MSI-X, bit15: MSI-X Enable
-        dev.location()
-            .write16(cap_ptr + 2, dev.location().read16(cap_ptr + 2) | 0x8000);
+        access.write16(cap_ptr + 2, access.read16(cap_ptr + 2) | 0x8000);
         // disable INTx, enable Bus master.
-        dev.set_command(dev.command() | Command::INTERRUPT_DISABLE | Command::BUS_MASTER);
+        let header = PciConfigHeader::new(access);
+        header.set_command(header.command() | Command::INTERRUPT_DISABLE | Command::BUS_MASTER);
 
         let mut irqs = Vec::with_capacity(table_size as usize);
         for i in 0..table_size {
             irqs.push(None);
         }
 
-        Self {
+        Ok(Self {
             loc: *dev.location(),
             ptr: cap_ptr,
-            table_size: (dev.location().read16(cap_ptr + 2) & 0b11_1111_1111) + 1,
+            table_size: (access.read16(cap_ptr + 2) & 0b11_1111_1111) + 1,
             table_bar,
             pending_table_bar: pba_bar,
             irqs,
             table_offset,
             pending_table_offset: pba_offset,
-        }
+        })
     }
 
     /// MSI-X Table size
@@ -213,6 +229,28 @@ impl CapabilityMsixData {
             .unwrap();
     }
 
+    /// Masks or unmasks an MSI-X vector.
+    ///
+    /// A masked vector does not send interrupts. If the device has an interrupt
+    /// to send while the vector is masked, it sets the pending bit of the
+    /// vector instead, which can be checked with [`Self::is_pending`].
+    pub fn mask_vector(&self, index: u16, masked: bool) {
+        if index >= self.table_size {
+            return;
+        }
+        set_vector_masked(&*self.table_bar, self.table_offset, index, masked);
+    }
+
+    /// Returns true if the MSI-X vector has a pending interrupt.
+    ///
+    /// Returns false if the index is out of the table.
+    pub fn is_pending(&self, index: u16) -> bool {
+        if index >= self.table_size {
+            return false;
+        }
+        is_vector_pending(&*self.pending_table_bar, self.pending_table_offset, index)
+    }
+
     /// Gets mutable IrqLine. User can register callbacks by using this function.
     pub fn irq_mut(&mut self, index: usize) -> Option<&mut IrqLine> {
         self.irqs[index].as_mut()
@@ -223,8 +261,143 @@ impl CapabilityMsixData {
         let msg_ctrl = self.loc.read16(self.ptr + 2);
         msg_ctrl & 0x8000 != 0
     }
+
+    /// Sets or clears the MSI-X Enable bit.
+    pub fn set_enabled(&self, enabled: bool) {
+        set_msg_ctrl_bit(self.loc, self.ptr, MSIX_CTRL_ENABLE_BIT, enabled);
+    }
+
+    /// Sets or clears the Function Mask bit.
+    ///
+    /// While the function is masked, all the vectors are masked regardless of
+    /// their own Mask bits. This is useful for quiescing the interrupts of a
+    /// device, e.g., during a reset.
+    pub fn set_function_mask(&self, masked: bool) {
+        set_msg_ctrl_bit(self.loc, self.ptr, MSIX_CTRL_FUNCTION_MASK_BIT, masked);
+    }
 }
 
 fn set_bit(origin_value: u16, offset: usize, set: bool) -> u16 {
     (origin_value & (!(1 << offset))) | ((set as u16) << offset)
 }
+
+/// Sets or clears a bit of the Message Control word of the MSI-X capability at `cap_ptr`.
+///
+/// The other bits of the word are preserved.
+fn set_msg_ctrl_bit(access: impl PciConfigAccess, cap_ptr: u16, bit: usize, set: bool) {
+    let msg_ctrl = access.read16(cap_ptr + 2);
+    access.write16(cap_ptr + 2, set_bit(msg_ctrl, bit, set));
+}
+
+/// Dword accesses to the memory of the MSI-X table or the Pending Bit Array (PBA).
+trait MsixMemAccess {
+    fn read32(&self, offset: usize) -> u32;
+    fn write32(&self, offset: usize, val: u32);
+}
+
+impl MsixMemAccess for MemoryBar {
+    fn read32(&self, offset: usize) -> u32 {
+        self.io_mem().read_once(offset).unwrap()
+    }
+
+    fn write32(&self, offset: usize, val: u32) {
+        self.io_mem().write_once(offset, &val).unwrap()
+    }
+}
+
+/// Sets the Mask bit of the `index`-th entry in the MSI-X table at `table_offset`.
+///
+/// The other bits of the Vector Control dword are preserved.
+fn set_vector_masked(table: &impl MsixMemAccess, table_offset: usize, index: u16, masked: bool) {
+    let offset = table_offset + MSIX_ENTRY_SIZE * index as usize + MSIX_VECTOR_CONTROL_OFFSET;
+    let control = table.read32(offset);
+    let control = if masked {
+        control | MSIX_VECTOR_CONTROL_MASK
+    } else {
+        control & !MSIX_VECTOR_CONTROL_MASK
+    };
+    table.write32(offset, control);
+}
+
+/// Reads the pending bit of the `index`-th vector in the PBA at `pba_offset`.
+fn is_vector_pending(pba: &impl MsixMemAccess, pba_offset: usize, index: u16) -> bool {
+    // The PBA is an array of bits, one for each vector, packed into dwords.
+    let offset = pba_offset + (index as usize / 32) * size_of::<u32>();
+    pba.read32(offset) & (1 << (index % 32)) != 0
+}
+
+#[cfg(ktest)]
+mod test {
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{bus::pci::mock::MockConfigSpace, prelude::*};
+
+    /// A mock MSI-X table or PBA backed by memory.
+    struct MockMsixMem {
+        dwords: RefCell<Vec<u32>>,
+    }
+
+    impl MockMsixMem {
+        fn new(dwords: &[u32]) -> Self {
+            Self {
+                dwords: RefCell::new(dwords.to_vec()),
+            }
+        }
+    }
+
+    impl MsixMemAccess for MockMsixMem {
+        fn read32(&self, offset: usize) -> u32 {
+            self.dwords.borrow()[offset / 4]
+        }
+
+        fn write32(&self, offset: usize, val: u32) {
+            self.dwords.borrow_mut()[offset / 4] = val;
+        }
+    }
+
+    #[ktest]
+    fn mask_vector_control() {
+        // Two table entries after a 16-byte gap, with reserved bits set
+        // in the Vector Control dword of the second entry.
+        let table = MockMsixMem::new(&[0; 12]);
+        table.write32(16 + 16 + 12, 0xf0);
+
+        set_vector_masked(&table, 16, 1, true);
+        assert_eq!(table.read32(16 + 16 + 12), 0xf1);
+        set_vector_masked(&table, 16, 1, false);
+        assert_eq!(table.read32(16 + 16 + 12), 0xf0);
+
+        // Other entries and fields are untouched.
+        set_vector_masked(&table, 16, 0, true);
+        assert_eq!(
+            *table.dwords.borrow(),
+            [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0xf0]
+        );
+    }
+
+    #[ktest]
+    fn toggle_msg_ctrl_bits() {
+        // The capability ID, the next pointer and a table size of 8.
+        let space = MockConfigSpace::new();
+        space.set_bytes(0x40, &[0x11, 0x00, 0x07, 0x00]);
+        let msg_ctrl = || (&space).read16(0x42);
+
+        set_msg_ctrl_bit(&space, 0x40, MSIX_CTRL_FUNCTION_MASK_BIT, true);
+        assert_eq!(msg_ctrl(), 0x4007);
+        set_msg_ctrl_bit(&space, 0x40, MSIX_CTRL_ENABLE_BIT, true);
+        assert_eq!(msg_ctrl(), 0xc007);
+        set_msg_ctrl_bit(&space, 0x40, MSIX_CTRL_FUNCTION_MASK_BIT, false);
+        assert_eq!(msg_ctrl(), 0x8007);
+        set_msg_ctrl_bit(&space, 0x40, MSIX_CTRL_ENABLE_BIT, false);
+        assert_eq!(msg_ctrl(), 0x0007);
+        assert_eq!((&space).read16(0x40), 0x0011);
+    }
+
+    #[ktest]
+    fn decode_pending_bits() {
+        let pba = MockMsixMem::new(&[0, 0b1001, 1 << 31]);
+        let pending = (0..64).filter(|i| is_vector_pending(&pba, 4, *i));
+        assert!(pending.eq([0, 3, 63]));
+    }
+}
@@ -151,6 +151,138 @@ bitflags! {
     }
 }
 
+/// Raw access to the configuration space of a PCI device.
+///
+/// The offsets are in bytes. Unaligned accesses within a dword are allowed.
+pub trait PciConfigAccess {
+    /// Reads a byte at `offset`.
+    fn read8(&self, offset: u16) -> u8;
+    /// Reads a word at `offset`.
+    fn read16(&self, offset: u16) -> u16;
+    /// Reads a dword at `offset`.
+    fn read32(&self, offset: u16) -> u32;
+    /// Writes a word at `offset`.
+    fn write16(&self, offset: u16, val: u16);
+    /// Writes a dword at `offset`.
+    fn write32(&self, offset: u16, val: u32);
+}
+
+impl PciConfigAccess for PciDeviceLocation {
+    fn read8(&self, offset: u16) -> u8 {
+        PciDeviceLocation::read8(self, offset)
+    }
+
+    fn read16(&self, offset: u16) -> u16 {
+        PciDeviceLocation::read16(self, offset)
+    }
+
+    fn read32(&self, offset: u16) -> u32 {
+        PciDeviceLocation::read32(self, offset)
+    }
+
+    fn write16(&self, offset: u16, val: u16) {
+        PciDeviceLocation::write16(self, offset, val)
+    }
+
+    fn write32(&self, offset: u16, val: u32) {
+        PciDeviceLocation::write32(self, offset, val)
+    }
+}
+
+/// Typed access to the fields of the common configuration space header.
+///
+/// The fields are read from and written to the configuration space directly
+/// through the configured access method, so no value is cached.
+#[derive(Debug, Clone, Copy)]
+pub struct PciConfigHeader<A: PciConfigAccess = PciDeviceLocation> {
+    access: A,
+}
+
+impl<A: PciConfigAccess> PciConfigHeader<A> {
+    /// Creates the header accessor.
+    pub fn new(access: A) -> Self {
+        Self { access }
+    }
+
+    /// Gets the PCI command.
+    pub fn command(&self) -> Command {
+        Command::from_bits_truncate(self.read16(PciDeviceCommonCfgOffset::Command))
+    }
+
+    /// Sets the PCI command.
+    pub fn set_command(&self, command: Command) {
+        self.access
+            .write16(PciDeviceCommonCfgOffset::Command as u16, command.bits())
+    }
+
+    /// Enables the device to respond to I/O space accesses,
+    /// so that its I/O BARs are decoded.
+    pub fn enable_io_space(&self) {
+        self.enable_command(Command::IO_SPACE);
+    }
+
+    /// Enables the device to respond to memory space accesses,
+    /// so that its memory BARs are decoded.
+    pub fn enable_memory_space(&self) {
+        self.enable_command(Command::MEMORY_SPACE);
+    }
+
+    /// Enables the device to act as a bus master, which is required for DMA.
+    pub fn enable_bus_master(&self) {
+        self.enable_command(Command::BUS_MASTER);
+    }
+
+    /// Sets the `bits` in the PCI command while leaving the other bits untouched.
+    fn enable_command(&self, bits: Command) {
+        let offset = PciDeviceCommonCfgOffset::Command as u16;
+        let raw = self.access.read16(offset);
+        self.access.write16(offset, raw | bits.bits());
+    }
+
+    /// Gets the PCI status.
+    pub fn status(&self) -> Status {
+        Status::from_bits_truncate(self.read16(PciDeviceCommonCfgOffset::Status))
+    }
+
+    /// Gets the revision ID.
+    pub fn revision(&self) -> u8 {
+        self.read8(PciDeviceCommonCfgOffset::RevisionId)
+    }
+
+    /// Gets the header type, without the multi-function bit.
+    ///
+    /// The header type is 0 for general devices, 1 for PCI-to-PCI bridges,
+    /// and 2 for CardBus bridges.
+    pub fn header_type(&self) -> u8 {
+        self.read8(PciDeviceCommonCfgOffset::HeaderType) & !Self::MULTI_FUNCTION_BIT
+    }
+
+    /// Returns whether the device has multiple functions.
+    pub fn is_multi_function(&self) -> bool {
+        self.read8(PciDeviceCommonCfgOffset::HeaderType) & Self::MULTI_FUNCTION_BIT != 0
+    }
+
+    /// Gets the offset of the first capability, or `None` if the device
+    /// does not support the capability list.
+    pub fn cap_ptr(&self) -> Option<u8> {
+        if !self.status().contains(Status::CAPABILITIES_LIST) {
+            return None;
+        }
+        // The bottom two bits are reserved.
+        Some(self.read8(PciDeviceCommonCfgOffset::CapabilitiesPointer) & 0xFC)
+    }
+
+    const MULTI_FUNCTION_BIT: u8 = 1 << 7;
+
+    fn read8(&self, offset: PciDeviceCommonCfgOffset) -> u8 {
+        self.access.read8(offset as u16)
+    }
+
+    fn read16(&self, offset: PciDeviceCommonCfgOffset) -> u16 {
+        self.access.read16(offset as u16)
+    }
+}
+
 /// BAR space in PCI common config space.
 #[derive(Debug, Clone)]
 pub enum Bar {
@@ -161,21 +293,21 @@ pub enum Bar {
 }
 
 impl Bar {
-    pub(super) fn new(location: PciDeviceLocation, index: u8) -> Result<Self> {
+    pub(super) fn new(access: &impl PciConfigAccess, index: u8) -> Result<Self> {
         if index >= 6 {
             return Err(Error::InvalidArgs);
         }
         // Get the original value first, then write all 1 to the register to get the length
-        let raw = location.read32(index as u16 * 4 + PciDeviceCommonCfgOffset::Bar0 as u16);
+        let raw = access.read32(index as u16 * 4 + PciDeviceCommonCfgOffset::Bar0 as u16);
         if raw == 0 {
             // no BAR
             return Err(Error::InvalidArgs);
         }
         Ok(if raw & 1 == 0 {
-            Self::Memory(Arc::new(MemoryBar::new(&location, index)?))
+            Self::Memory(Arc::new(MemoryBar::new(access, index)?))
         } else {
             // IO BAR
-            Self::Io(Arc::new(IoBar::new(&location, index)?))
+            Self::Io(Arc::new(IoBar::new(access, index)?))
         })
     }
 
@@ -234,13 +366,13 @@ impl MemoryBar {
     }
 
     /// Creates a memory BAR structure.
-    fn new(location: &PciDeviceLocation, index: u8) -> Result<Self> {
+    fn new(access: &impl PciConfigAccess, index: u8) -> Result<Self> {
         // Get the original value first, then write all 1 to the register to get the length
         let offset = index as u16 * 4 + PciDeviceCommonCfgOffset::Bar0 as u16;
-        let raw = location.read32(offset);
-        location.write32(offset, !0);
-        let len_encoded = location.read32(offset);
-        location.write32(offset, raw);
+        let raw = access.read32(offset);
+        access.write32(offset, !0);
+        let len_encoded = access.read32(offset);
+        access.write32(offset, raw);
         let mut address_length = AddrLen::Bits32;
         // base address, it may be bit64 or bit32
         let base: u64 = match (raw & 0b110) >> 1 {
@@ -249,7 +381,7 @@ impl MemoryBar {
             // bits64
             2 => {
                 address_length = AddrLen::Bits64;
-                ((raw & !0xF) as u64) | ((location.read32(offset + 4) as u64) << 32)
+                ((raw & !0xF) as u64) | ((access.read32(offset + 4) as u64) << 32)
             }
             _ => {
                 return Err(Error::InvalidArgs);
@@ -333,12 +465,12 @@ impl IoBar {
         Ok(())
     }
 
-    fn new(location: &PciDeviceLocation, index: u8) -> Result<Self> {
+    fn new(access: &impl PciConfigAccess, index: u8) -> Result<Self> {
         let offset = index as u16 * 4 + PciDeviceCommonCfgOffset::Bar0 as u16;
-        let raw = location.read32(offset);
-        location.write32(offset, !0);
-        let len_encoded = location.read32(offset);
-        location.write32(offset, raw);
+        let raw = access.read32(offset);
+        access.write32(offset, !0);
+        let len_encoded = access.read32(offset);
+        access.write32(offset, raw);
         let len = !(len_encoded & !0x3) + 1;
         Ok(Self {
             base: raw & !0x3,
@@ -346,3 +478,62 @@ impl IoBar {
         })
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use super::*;
+    use crate::{bus::pci::mock::MockConfigSpace, prelude::*};
+
+    #[ktest]
+    fn config_header_fields() {
+        let space = MockConfigSpace::new();
+        space.set_bytes(0x04, &0x0006u16.to_le_bytes());
+        space.set_bytes(0x06, &0x0010u16.to_le_bytes());
+        space.set_bytes(0x08, &[0x5A]);
+        space.set_bytes(0x0E, &[0x81]);
+        space.set_bytes(0x34, &[0x43]);
+
+        let header = PciConfigHeader::new(&space);
+        assert_eq!(
+            header.command(),
+            Command::MEMORY_SPACE | Command::BUS_MASTER
+        );
+        assert_eq!(header.status(), Status::CAPABILITIES_LIST);
+        assert_eq!(header.revision(), 0x5A);
+        assert_eq!(header.header_type(), 1);
+        assert!(header.is_multi_function());
+        assert_eq!(header.cap_ptr(), Some(0x40));
+
+        header.set_command(Command::IO_SPACE);
+        assert_eq!((&space).read16(0x04), 0x0001);
+        // The neighboring status register is untouched.
+        assert_eq!((&space).read16(0x06), 0x0010);
+
+        // Without the capabilities list bit, the capability pointer is invalid.
+        space.set_bytes(0x06, &[0, 0]);
+        assert_eq!(header.cap_ptr(), None);
+    }
+
+    #[ktest]
+    fn enable_command_bits() {
+        // Bit 7 and bit 11 are not defined in `Command`, but must be preserved.
+        const RESERVED: u16 = (1 << 7) | (1 << 11);
+
+        let space = MockConfigSpace::new();
+        space.set_bytes(0x04, &RESERVED.to_le_bytes());
+        space.set_bytes(0x06, &0x0010u16.to_le_bytes());
+        let header = PciConfigHeader::new(&space);
+
+        header.enable_bus_master();
+        assert_eq!((&space).read16(0x04), RESERVED | (1 << 2));
+        header.enable_memory_space();
+        assert_eq!((&space).read16(0x04), RESERVED | (1 << 2) | (1 << 1));
+        header.enable_io_space();
+        assert_eq!((&space).read16(0x04), RESERVED | 0b111);
+        // Enabling an enabled bit changes nothing.
+        header.enable_bus_master();
+        assert_eq!((&space).read16(0x04), RESERVED | 0b111);
+
+        assert_eq!((&space).read16(0x06), 0x0010);
+    }
+}
@@ -9,9 +9,13 @@ use alloc::vec::Vec;
 
 use super::{
     capability::Capability,
-    cfg_space::{AddrLen, Bar, Command, PciDeviceCommonCfgOffset, Status},
+    cfg_space::{
+        AddrLen, Bar, Command, PciConfigAccess, PciConfigHeader, PciDeviceCommonCfgOffset, Status,
+    },
     device_info::{PciDeviceId, PciDeviceLocation},
+    ScanError,
 };
+use crate::trap::IrqLine;
 
 /// PCI common device, Contains a range of information and functions common to PCI devices.
 #[derive(Debug)]
@@ -43,45 +47,122 @@ impl PciCommonDevice {
         &self.capabilities
     }
 
+    /// Gets typed access to the common configuration space header.
+    pub fn config_header(&self) -> PciConfigHeader {
+        PciConfigHeader::new(self.location)
+    }
+
     /// Gets the PCI Command
     pub fn command(&self) -> Command {
-        Command::from_bits_truncate(
-            self.location
-                .read16(PciDeviceCommonCfgOffset::Command as u16),
-        )
+        self.config_header().command()
     }
 
     /// Sets the PCI Command
     pub fn set_command(&self, command: Command) {
-        self.location
-            .write16(PciDeviceCommonCfgOffset::Command as u16, command.bits())
+        self.config_header().set_command(command)
+    }
+
+    /// Enables the device to respond to I/O space accesses.
+    ///
+    /// Drivers should call this during initialization if the device has I/O BARs,
+    /// instead of relying on the firmware to have enabled it.
+    pub fn enable_io_space(&self) {
+        self.config_header().enable_io_space()
+    }
+
+    /// Enables the device to respond to memory space accesses.
+    ///
+    /// Drivers should call this during initialization if the device has memory BARs,
+    /// instead of relying on the firmware to have enabled it.
+    pub fn enable_memory_space(&self) {
+        self.config_header().enable_memory_space()
+    }
+
+    /// Enables the device to act as a bus master.
+    ///
+    /// Drivers should call this during initialization if the device performs DMA.
+    pub fn enable_bus_master(&self) {
+        self.config_header().enable_bus_master()
     }
 
     /// Gets the PCI status
     pub fn status(&self) -> Status {
-        Status::from_bits_truncate(
-            self.location
-                .read16(PciDeviceCommonCfgOffset::Status as u16),
-        )
+        self.config_header().status()
     }
 
-    pub(super) fn new(location: PciDeviceLocation) -> Option<Self> {
-        if location.read16(0) == 0xFFFF {
+    /// Allocates an IRQ line for the legacy INTx interrupt of the device.
+    ///
+    /// The IRQ is routed according to the interrupt line register, which is
+    /// set up by the firmware. On success, the INTx interrupt of the device is
+    /// enabled by clearing [`Command::INTERRUPT_DISABLE`].
+    ///
+    /// Returns `None` if the device does not use INTx, or if the interrupt line
+    /// cannot be routed. Sharing an interrupt line among devices is not
+    /// supported yet, so this fails if another device has taken the line.
+    pub fn alloc_legacy_irq(&self) -> Option<IrqLine> {
+        let pin = self
+            .location
+            .read8(PciDeviceCommonCfgOffset::InterruptPin as u16);
+        let line = self
+            .location
+            .read8(PciDeviceCommonCfgOffset::InterruptLine as u16);
+        // Pin 0 means INTx is not used, and line 0xFF means "unknown" or "no connection".
+        if pin == 0 || line == 0xFF {
+            return None;
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let irq = IrqLine::alloc().ok()?;
+            let io_apics = crate::arch::kernel::IO_APIC.get()?;
+            // FIXME: This assumes that the interrupt line is identity-mapped to
+            // the pins of the first I/O APIC, which holds on QEMU.
+            if let Err(err) = io_apics.first()?.lock().enable(line, irq.clone()) {
+                log::warn!(
+                    "{:?}: cannot route INTx line {} ({:?}), which may be shared with \
+                     another device and shared INTx lines are not supported",
+                    self.location,
+                    line,
+                    err
+                );
+                return None;
+            }
+            self.set_command(self.command() - Command::INTERRUPT_DISABLE);
+            Some(irq)
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        None
+    }
+
+    /// Probes the device at the location, whose configuration space is accessed
+    /// through `access`.
+    ///
+    /// The configuration space of a real device is accessed through its location.
+    ///
+    /// Returns `None` if there is no device, or an error if the device is malformed.
+    pub(super) fn new(
+        location: PciDeviceLocation,
+        access: impl PciConfigAccess + Copy,
+    ) -> Option<Result<Self, ScanError>> {
+        if access.read16(0) == 0xFFFF {
             // not exists
             return None;
         }
 
         let capabilities = Vec::new();
-        let device_id = PciDeviceId::new(location);
-        let bar_manager = BarManager::new(location);
+        let device_id = PciDeviceId::new(access);
+        let bar_manager = BarManager::new(access);
         let mut device = Self {
             device_id,
             location,
             bar_manager,
             capabilities,
         };
-        device.capabilities = Capability::device_capabilities(&mut device);
-        Some(device)
+        device.capabilities = match Capability::device_capabilities(&mut device, access) {
+            Ok(capabilities) => capabilities,
+            Err(err) => return Some(Err(ScanError::Capability(location, err))),
+        };
+        Some(Ok(device))
     }
 
     pub(super) fn bar_manager_mut(&mut self) -> &mut BarManager {
@@ -111,9 +192,9 @@ impl BarManager {
         }
     }
 
-    /// Parse the BAR space by PCI device location.
-    fn new(location: PciDeviceLocation) -> Self {
-        let header_type = location.read8(PciDeviceCommonCfgOffset::HeaderType as u16) & !(1 << 7);
+    /// Parse the BAR space through the configuration space access.
+    fn new(access: impl PciConfigAccess) -> Self {
+        let header_type = access.read8(PciDeviceCommonCfgOffset::HeaderType as u16) & !(1 << 7);
         // Get the max bar amount, header type=0 => end device; header type=1 => PCI bridge.
         let max = match header_type {
             0 => 6,
@@ -123,7 +204,7 @@ impl BarManager {
         let mut idx = 0;
         let mut bars = [None, None, None, None, None, None];
         while idx < max {
-            if let Ok(bar) = Bar::new(location, idx) {
+            if let Ok(bar) = Bar::new(&access, idx) {
                 let mut idx_step = 0;
                 match &bar {
                     Bar::Memory(memory_bar) => {
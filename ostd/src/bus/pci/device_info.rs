@@ -4,8 +4,77 @@
 
 use core::iter;
 
-use super::cfg_space::PciDeviceCommonCfgOffset;
-use crate::arch::pci::{PCI_ADDRESS_PORT, PCI_DATA_PORT};
+use spin::Once;
+
+use super::cfg_space::{PciConfigAccess, PciDeviceCommonCfgOffset};
+use crate::{
+    arch::pci::{PCI_ADDRESS_PORT, PCI_DATA_PORT},
+    io_mem::IoMem,
+    mm::{
+        page_prop::{CachePolicy, PageFlags},
+        Paddr, VmIoOnce,
+    },
+};
+
+/// The method to access the PCI configuration space.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CSpaceAccessMethod {
+    /// Accesses the configuration space through the `0xCF8`/`0xCFC` I/O port pair.
+    Io,
+    /// Accesses the configuration space through the memory-mapped Enhanced
+    /// Configuration Access Mechanism (ECAM) region of the buses from `start_bus`
+    /// to `end_bus`.
+    Mmio {
+        /// The physical base address of the ECAM region, which corresponds to bus 0
+        /// even if `start_bus` is not 0.
+        base: Paddr,
+        /// The first bus covered by the ECAM region.
+        start_bus: u8,
+        /// The last bus covered by the ECAM region.
+        end_bus: u8,
+    },
+}
+
+/// The configuration space accessor, set up by [`init_cspace_access`].
+///
+/// The configuration space is accessed through the I/O ports if it is not set up.
+static CSPACE_ACCESS: Once<CSpaceAccess> = Once::new();
+
+enum CSpaceAccess {
+    Io,
+    /// The ECAM region of the buses from `start_bus` to `end_bus`. The devices on
+    /// the other buses are absent.
+    Mmio {
+        io_mem: IoMem,
+        start_bus: u8,
+        end_bus: u8,
+    },
+}
+
+/// Sets up the method to access the PCI configuration space.
+///
+/// Only the first call takes effect. The subsequent calls are ignored.
+pub(super) fn init_cspace_access(method: CSpaceAccessMethod) {
+    CSPACE_ACCESS.call_once(|| match method {
+        CSpaceAccessMethod::Io => CSpaceAccess::Io,
+        CSpaceAccessMethod::Mmio {
+            base,
+            start_bus,
+            end_bus,
+        } => {
+            let start = base + PciDeviceLocation::ecam_offset_of(start_bus as u32, 0, 0, 0);
+            let end = base + PciDeviceLocation::ecam_offset_of(end_bus as u32 + 1, 0, 0, 0);
+            // SAFETY: The ECAM region is reported by the firmware to be the I/O memory
+            // region of the PCI configuration space.
+            let io_mem = unsafe { IoMem::new(start..end, PageFlags::RW, CachePolicy::Uncacheable) };
+            CSpaceAccess::Mmio {
+                io_mem,
+                start_bus,
+                end_bus,
+            }
+        }
+    });
+}
 
 /// PCI device ID
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -29,16 +98,15 @@ pub struct PciDeviceId {
 }
 
 impl PciDeviceId {
-    pub(super) fn new(location: PciDeviceLocation) -> Self {
-        let vendor_id = location.read16(PciDeviceCommonCfgOffset::VendorId as u16);
-        let device_id = location.read16(PciDeviceCommonCfgOffset::DeviceId as u16);
-        let revision_id = location.read8(PciDeviceCommonCfgOffset::RevisionId as u16);
-        let prog_if = location.read8(PciDeviceCommonCfgOffset::ClassCode as u16);
-        let subclass = location.read8(PciDeviceCommonCfgOffset::ClassCode as u16 + 1);
-        let class = location.read8(PciDeviceCommonCfgOffset::ClassCode as u16 + 2);
-        let subsystem_vendor_id =
-            location.read16(PciDeviceCommonCfgOffset::SubsystemVendorId as u16);
-        let subsystem_id = location.read16(PciDeviceCommonCfgOffset::SubsystemId as u16);
+    pub(super) fn new(access: impl PciConfigAccess) -> Self {
+        let vendor_id = access.read16(PciDeviceCommonCfgOffset::VendorId as u16);
+        let device_id = access.read16(PciDeviceCommonCfgOffset::DeviceId as u16);
+        let revision_id = access.read8(PciDeviceCommonCfgOffset::RevisionId as u16);
+        let prog_if = access.read8(PciDeviceCommonCfgOffset::ClassCode as u16);
+        let subclass = access.read8(PciDeviceCommonCfgOffset::ClassCode as u16 + 1);
+        let class = access.read8(PciDeviceCommonCfgOffset::ClassCode as u16 + 2);
+        let subsystem_vendor_id = access.read16(PciDeviceCommonCfgOffset::SubsystemVendorId as u16);
+        let subsystem_id = access.read16(PciDeviceCommonCfgOffset::SubsystemId as u16);
         Self {
             vendor_id,
             device_id,
@@ -80,6 +148,32 @@ impl PciDeviceLocation {
             | (((self.function as u32) & 0b111) << 8)
     }
 
+    /// Returns the offset in the ECAM region to access the configuration space of this
+    /// device at `offset`.
+    pub fn encode_as_ecam_offset(self, offset: u16) -> usize {
+        Self::ecam_offset_of(
+            self.bus as u32,
+            self.device as u32 & 0b11111,
+            self.function as u32 & 0b111,
+            offset as u32 & 0xFFF,
+        )
+    }
+
+    /// Returns the offset in the ECAM region of the buses from `start_bus` to `end_bus`
+    /// to access the configuration space of this device at `offset`.
+    ///
+    /// Returns `None` if the device is not on the buses.
+    fn encode_as_ecam_offset_in(self, start_bus: u8, end_bus: u8, offset: u16) -> Option<usize> {
+        if !(start_bus..=end_bus).contains(&self.bus) {
+            return None;
+        }
+        Some(self.encode_as_ecam_offset(offset) - Self::ecam_offset_of(start_bus as u32, 0, 0, 0))
+    }
+
+    const fn ecam_offset_of(bus: u32, device: u32, function: u32, offset: u32) -> usize {
+        ((bus << 20) | (device << 15) | (function << 12) | offset) as usize
+    }
+
     /// Returns an iterator that enumerates all possible PCI device locations.
     pub fn all() -> impl Iterator<Item = PciDeviceLocation> {
         iter::from_coroutine(
@@ -130,6 +224,22 @@ impl PciDeviceLocation {
             (offset & 0b11) == 0,
             "misaligned PCI configuration dword u32 read"
         );
+        if let Some(CSpaceAccess::Mmio {
+            io_mem,
+            start_bus,
+            end_bus,
+        }) = CSPACE_ACCESS.get()
+        {
+            let Some(ecam_offset) = self.encode_as_ecam_offset_in(
+                *start_bus,
+                *end_bus,
+                offset & Self::BIT32_ALIGN_MASK,
+            ) else {
+                // Reads from absent devices return all ones.
+                return u32::MAX;
+            };
+            return io_mem.read_once::<u32>(ecam_offset).unwrap().to_le();
+        }
         PCI_ADDRESS_PORT
             .write(self.encode_as_x86_address_value() | (offset & Self::BIT32_ALIGN_MASK) as u32);
         PCI_DATA_PORT.read().to_le()
@@ -161,8 +271,64 @@ impl PciDeviceLocation {
             "misaligned PCI configuration dword u32 write"
         );
 
+        if let Some(CSpaceAccess::Mmio {
+            io_mem,
+            start_bus,
+            end_bus,
+        }) = CSPACE_ACCESS.get()
+        {
+            // Writes to absent devices are dropped.
+            if let Some(ecam_offset) =
+                self.encode_as_ecam_offset_in(*start_bus, *end_bus, offset & Self::BIT32_ALIGN_MASK)
+            {
+                io_mem.write_once(ecam_offset, &val.to_le()).unwrap();
+            }
+            return;
+        }
         PCI_ADDRESS_PORT
             .write(self.encode_as_x86_address_value() | (offset & Self::BIT32_ALIGN_MASK) as u32);
         PCI_DATA_PORT.write(val.to_le())
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[ktest]
+    fn ecam_offset() {
+        let offset_of = |bus, device, function, offset| {
+            PciDeviceLocation {
+                bus,
+                device,
+                function,
+            }
+            .encode_as_ecam_offset(offset)
+        };
+        assert_eq!(offset_of(0, 0, 0, 0), 0);
+        assert_eq!(offset_of(0, 0, 0, 0x40), 0x40);
+        assert_eq!(offset_of(0, 0, 1, 0), 0x1000);
+        assert_eq!(offset_of(0, 1, 0, 0), 0x8000);
+        assert_eq!(offset_of(1, 0, 0, 0), 0x10_0000);
+        assert_eq!(offset_of(0x12, 3, 4, 0x10), 0x121_C010);
+        assert_eq!(offset_of(255, 31, 7, 0xFFC), 0xFFF_FFFC);
+    }
+
+    #[ktest]
+    fn ecam_offset_in_bus_range() {
+        let offset_in = |bus, offset| {
+            PciDeviceLocation {
+                bus,
+                device: 1,
+                function: 0,
+            }
+            .encode_as_ecam_offset_in(0x10, 0x1F, offset)
+        };
+        assert_eq!(offset_in(0x10, 0), Some(0x8000));
+        assert_eq!(offset_in(0x11, 0x40), Some(0x10_8040));
+        assert_eq!(offset_in(0x1F, 0xFFC), Some(0xF0_8FFC));
+        assert_eq!(offset_in(0x0F, 0), None);
+        assert_eq!(offset_in(0x20, 0), None);
+    }
+}
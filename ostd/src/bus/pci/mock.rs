@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A mock PCI configuration space for testing.
+
+use core::cell::RefCell;
+
+use super::cfg_space::PciConfigAccess;
+
+/// A mock configuration space backed by memory.
+pub(super) struct MockConfigSpace {
+    bytes: RefCell<[u8; 256]>,
+}
+
+impl MockConfigSpace {
+    pub(super) fn new() -> Self {
+        Self {
+            bytes: RefCell::new([0; 256]),
+        }
+    }
+
+    pub(super) fn set_bytes(&self, offset: u16, bytes: &[u8]) {
+        let offset = offset as usize;
+        self.bytes.borrow_mut()[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+impl PciConfigAccess for &MockConfigSpace {
+    fn read8(&self, offset: u16) -> u8 {
+        self.bytes.borrow()[offset as usize]
+    }
+
+    fn read16(&self, offset: u16) -> u16 {
+        let offset = offset as usize;
+        let bytes = self.bytes.borrow();
+        u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn read32(&self, offset: u16) -> u32 {
+        let offset = offset as usize;
+        let bytes = self.bytes.borrow();
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn write16(&self, offset: u16, val: u16) {
+        self.set_bytes(offset, &val.to_le_bytes());
+    }
+
+    fn write32(&self, offset: u16, val: u32) {
+        self.set_bytes(offset, &val.to_le_bytes());
+    }
+}
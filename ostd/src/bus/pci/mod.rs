@@ -55,21 +55,176 @@ pub mod capability;
 pub mod cfg_space;
 pub mod common_device;
 mod device_info;
+#[cfg(ktest)]
+mod mock;
 
-pub use device_info::{PciDeviceId, PciDeviceLocation};
+use alloc::{sync::Arc, vec::Vec};
 
-use self::{bus::PciBus, common_device::PciCommonDevice};
+pub use device_info::{CSpaceAccessMethod, PciDeviceId, PciDeviceLocation};
+use log::info;
+
+use self::{
+    bus::{PciBus, PciDevice},
+    capability::CapabilityError,
+    common_device::PciCommonDevice,
+};
 use crate::sync::Mutex;
 
 /// PCI bus instance
 pub static PCI_BUS: Mutex<PciBus> = Mutex::new(PciBus::new());
 
 pub(crate) fn init() {
+    scan_bus(crate::arch::pci::cspace_access_method());
+}
+
+/// Errors that occur when scanning a PCI device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanError {
+    /// The capabilities of the device at the location are malformed.
+    Capability(PciDeviceLocation, CapabilityError),
+}
+
+/// Scans all the PCI device locations with the given configuration space access method,
+/// and registers the found devices to the PCI bus.
+fn scan_bus(method: CSpaceAccessMethod) {
+    device_info::init_cspace_access(method);
+    rescan();
+}
+
+/// Rescans the PCI bus for the devices that are not found by the previous scans,
+/// e.g., the hot-plugged devices.
+///
+/// The newly found devices are registered to the PCI bus and probed by the registered
+/// drivers. The devices found before are left untouched. The malformed devices are
+/// skipped without stopping the scan.
+///
+/// Returns the locations of the newly found devices.
+pub fn rescan() -> Vec<PciDeviceLocation> {
     let mut lock = PCI_BUS.lock();
-    for location in PciDeviceLocation::all() {
-        let Some(device) = PciCommonDevice::new(location) else {
-            continue;
+    let new_devices = scan_new_devices(
+        PciDeviceLocation::all(),
+        |location| lock.is_registered(location),
+        |location| PciCommonDevice::new(location, location),
+    );
+
+    let mut new_locations = Vec::with_capacity(new_devices.len());
+    for device in new_devices {
+        match device {
+            Ok(device) => {
+                new_locations.push(*device.location());
+                lock.register_common_device(device);
+            }
+            Err(err) => info!("Skip a malformed PCI device: {:?}", err),
+        }
+    }
+    new_locations
+}
+
+/// Calls `f` on each PCI device claimed by a driver.
+///
+/// The PCI bus is locked only once for all the devices, so `f` must not
+/// access [`PCI_BUS`]. Use [`collect_claimed_devices`] if it needs to.
+pub fn for_each_claimed_device(f: impl FnMut(&Arc<dyn PciDevice>)) {
+    PCI_BUS.lock().for_each_claimed_device(f);
+}
+
+/// Returns a snapshot of the PCI devices claimed by drivers.
+pub fn collect_claimed_devices() -> Vec<Arc<dyn PciDevice>> {
+    PCI_BUS.lock().collect_claimed_devices()
+}
+
+/// Probes the locations that are not known yet, and returns the devices found.
+fn scan_new_devices<D>(
+    locations: impl Iterator<Item = PciDeviceLocation>,
+    is_known: impl Fn(&PciDeviceLocation) -> bool,
+    probe: impl FnMut(PciDeviceLocation) -> Option<D>,
+) -> Vec<D> {
+    locations
+        .filter(|location| !is_known(location))
+        .filter_map(probe)
+        .collect()
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::collections::BTreeSet;
+
+    use super::*;
+    use crate::{
+        bus::pci::{cfg_space::Status, mock::MockConfigSpace},
+        prelude::*,
+    };
+
+    fn location(bus: u8, device: u8, function: u8) -> PciDeviceLocation {
+        PciDeviceLocation {
+            bus,
+            device,
+            function,
+        }
+    }
+
+    #[ktest]
+    fn rescan_finds_only_new_devices() {
+        let mut present = BTreeSet::from([location(0, 1, 0), location(0, 2, 0)]);
+        let mut known = BTreeSet::new();
+        let mut rescan = |present: &BTreeSet<PciDeviceLocation>| {
+            let new_devices = scan_new_devices(
+                PciDeviceLocation::all(),
+                |location| known.contains(location),
+                |location| present.contains(&location).then_some(location),
+            );
+            known.extend(new_devices.iter().copied());
+            new_devices
         };
-        lock.register_common_device(device);
+
+        assert_eq!(rescan(&present), [location(0, 1, 0), location(0, 2, 0)]);
+        assert!(rescan(&present).is_empty());
+
+        present.insert(location(1, 0, 3));
+        assert_eq!(rescan(&present), [location(1, 0, 3)]);
+    }
+
+    #[ktest]
+    fn scan_skips_malformed_devices() {
+        let status = Status::CAPABILITIES_LIST.bits().to_le_bytes();
+        let good = MockConfigSpace::new();
+        good.set_bytes(0x06, &status);
+        good.set_bytes(0x34, &[0x40]);
+        good.set_bytes(0x40, &[0x01, 0x00]);
+        // The second capability points back into the common header.
+        let bad = MockConfigSpace::new();
+        bad.set_bytes(0x06, &status);
+        bad.set_bytes(0x34, &[0x40]);
+        bad.set_bytes(0x40, &[0x01, 0x10]);
+
+        let spaces = [
+            (location(0, 1, 0), &good),
+            (location(0, 2, 0), &bad),
+            (location(0, 3, 0), &good),
+        ];
+        let results = scan_new_devices(
+            PciDeviceLocation::all(),
+            |_| false,
+            |location| {
+                let (_, space) = spaces.iter().find(|(loc, _)| *loc == location)?;
+                PciCommonDevice::new(location, *space)
+            },
+        );
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|res| res.map(|device| *device.location()))
+            .collect();
+
+        assert_eq!(
+            results,
+            [
+                Ok(location(0, 1, 0)),
+                Err(ScanError::Capability(
+                    location(0, 2, 0),
+                    CapabilityError::InvalidPointer(0x10)
+                )),
+                Ok(location(0, 3, 0)),
+            ]
+        );
     }
 }
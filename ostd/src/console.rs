@@ -6,6 +6,11 @@ use core::fmt::Arguments;
 
 /// Prints formatted arguments to the console.
 pub fn early_print(args: Arguments) {
+    #[cfg(ktest)]
+    if capture::try_capture(args) {
+        return;
+    }
+
     crate::arch::serial::print(args);
 }
 
@@ -25,3 +30,113 @@ macro_rules! early_println {
         $crate::console::early_print(format_args!(concat!($fmt, "\n") $(, $($arg)+)?))
     }
 }
+
+#[cfg(ktest)]
+pub(crate) mod capture {
+    //! Capturing the console output in unit tests.
+
+    use alloc::{string::String, sync::Arc};
+    use core::fmt::{Arguments, Write};
+
+    use crate::{
+        sync::{LocalIrqDisabled, SpinLock},
+        task::Task,
+    };
+
+    /// The capture that is currently active, if any.
+    ///
+    /// It records the address of the capturing task and the buffer.
+    static ACTIVE_CAPTURE: SpinLock<Option<(usize, Arc<SpinLock<String>>)>, LocalIrqDisabled> =
+        SpinLock::new(None);
+
+    /// Starts capturing the output of [`early_print!`] into an in-memory buffer.
+    ///
+    /// The capture lasts until the returned guard is dropped, after which the
+    /// output goes to the console again.
+    ///
+    /// Only the output printed in the context of the current task is captured.
+    /// This includes the output of the interrupt handlers that happen to run
+    /// while the current task is running on the CPU. The output of other tasks
+    /// still goes to the console.
+    ///
+    /// There can be at most one active capture in the system. Creating a new
+    /// capture suspends the existing one until the new one is dropped. The
+    /// guards should therefore be dropped in the reverse order of creation.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called in the bootstrap context.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let capture = ostd::ktest::capture_output();
+    /// early_print!("hello");
+    /// assert_eq!(capture.output(), "hello");
+    /// ```
+    ///
+    /// [`early_print!`]: crate::early_print
+    pub fn capture_output() -> OutputCapture {
+        let task = Task::current().expect("output can only be captured in a task context");
+        let buffer = Arc::new(SpinLock::new(String::new()));
+
+        let previous = ACTIVE_CAPTURE
+            .lock()
+            .replace((task_addr(&task), buffer.clone()));
+
+        OutputCapture { buffer, previous }
+    }
+
+    /// A guard that captures the console output.
+    ///
+    /// See [`capture_output`] for more details.
+    #[must_use]
+    pub struct OutputCapture {
+        buffer: Arc<SpinLock<String>>,
+        previous: Option<(usize, Arc<SpinLock<String>>)>,
+    }
+
+    impl OutputCapture {
+        /// Returns a copy of the output captured so far.
+        pub fn output(&self) -> String {
+            self.buffer.disable_irq().lock().clone()
+        }
+    }
+
+    impl Drop for OutputCapture {
+        fn drop(&mut self) {
+            *ACTIVE_CAPTURE.lock() = self.previous.take();
+        }
+    }
+
+    /// Writes the arguments into the active capture, if the current task is
+    /// the capturing task.
+    ///
+    /// Returns whether the arguments have been captured.
+    pub(crate) fn try_capture(args: Arguments) -> bool {
+        let Some(task) = Task::current() else {
+            return false;
+        };
+
+        // Do not wait for the lock, or printing while holding the lock (e.g.,
+        // due to a panic) would result in a deadlock.
+        let Some(active) = ACTIVE_CAPTURE.try_lock() else {
+            return false;
+        };
+        let Some((capturing_task, buffer)) = active.as_ref() else {
+            return false;
+        };
+        if *capturing_task != task_addr(&task) {
+            return false;
+        }
+
+        let Some(mut buffer) = buffer.disable_irq().try_lock() else {
+            return false;
+        };
+        buffer.write_fmt(args).is_ok()
+    }
+
+    fn task_addr(task: &Task) -> usize {
+        task as *const Task as usize
+    }
+}
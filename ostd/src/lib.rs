@@ -130,7 +130,7 @@ fn invoke_ffi_init_funcs() {
 /// Simple unit tests for the ktest framework.
 #[cfg(ktest)]
 mod test {
-    use crate::prelude::*;
+    use crate::{early_print, early_println, prelude::*};
 
     #[ktest]
     #[allow(clippy::eq_op)]
@@ -149,6 +149,93 @@ mod test {
     fn expect_panic() {
         panic!("expected panic message");
     }
+
+    #[ktest]
+    #[ignore]
+    #[allow(clippy::eq_op)]
+    fn ignored_assertion() {
+        assert_eq!(1, 1);
+    }
+
+    #[ktest_each(cases = [1, 2, 3])]
+    fn parameterized_assertion(n: u32) {
+        assert_eq!(n * 2, n + n);
+    }
+
+    #[ktest(timeout_ms = 10_000)]
+    fn finish_within_timeout() {
+        // Spin for a few timer ticks so that the watchdog is checked while the test runs.
+        let start = crate::timer::Jiffies::elapsed().as_u64();
+        while crate::timer::Jiffies::elapsed().as_u64() < start + 10 {
+            core::hint::spin_loop();
+        }
+    }
+
+    #[ktest]
+    fn capture_output() {
+        let outer = crate::ktest::capture_output();
+        early_print!("outer ");
+        {
+            let inner = crate::ktest::capture_output();
+            early_println!("inner {}", 42);
+            assert_eq!(inner.output(), "inner 42\n");
+        }
+        early_print!("again");
+        assert_eq!(outer.output(), "outer again");
+    }
+
+    mod hooks {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::prelude::*;
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        static NR_SETUPS: AtomicUsize = AtomicUsize::new(0);
+        static NR_TEARDOWNS: AtomicUsize = AtomicUsize::new(0);
+
+        #[ktest_setup]
+        fn setup() {
+            // The teardown hook has run once after each earlier test in this module,
+            // whatever the order of the tests is and even if the test panicked.
+            assert_eq!(
+                NR_TEARDOWNS.load(Ordering::Relaxed),
+                NR_SETUPS.fetch_add(1, Ordering::Relaxed)
+            );
+            COUNTER.store(42, Ordering::Relaxed);
+        }
+
+        #[ktest_teardown]
+        fn teardown() {
+            COUNTER.store(0, Ordering::Relaxed);
+            NR_TEARDOWNS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        #[ktest]
+        fn setup_runs_before_test() {
+            assert_eq!(COUNTER.swap(1, Ordering::Relaxed), 42);
+        }
+
+        #[ktest]
+        fn setup_runs_before_each_test() {
+            assert_eq!(COUNTER.swap(2, Ordering::Relaxed), 42);
+        }
+
+        #[ktest]
+        #[should_panic]
+        fn setup_runs_before_panicking_test() {
+            assert_eq!(COUNTER.swap(3, Ordering::Relaxed), 42);
+            panic!("the teardown hook should still run");
+        }
+
+        #[ktest]
+        fn teardown_runs_after_each_test() {
+            // Only the teardown of this test is still pending.
+            assert_eq!(
+                NR_TEARDOWNS.load(Ordering::Relaxed) + 1,
+                NR_SETUPS.load(Ordering::Relaxed)
+            );
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -161,4 +248,6 @@ pub mod ktest {
 
     pub use ostd_macros::{test_main as main, test_panic_handler as panic_handler};
     pub use ostd_test::*;
+
+    pub use crate::console::capture::{capture_output, OutputCapture};
 }
@@ -10,7 +10,7 @@ pub type Result<T> = core::result::Result<T, crate::error::Error>;
 pub(crate) use alloc::{boxed::Box, sync::Arc, vec::Vec};
 
 #[cfg(ktest)]
-pub use ostd_macros::ktest;
+pub use ostd_macros::{ktest, ktest_each, ktest_setup, ktest_teardown};
 
 pub use crate::{
     early_print as print, early_println as println,